@@ -1,3 +1,4 @@
+use std::hash::{Hash, Hasher};
 use std::ops::{Add, Sub, Neg, Mul, Div};
 use std::cmp::Ordering;
 
@@ -6,7 +7,8 @@ pub type UVector = Vector<usize>;
 pub type FVector = Vector<f64>;
 
 /// 2D generic vector (horizontal, vertical). It's implemented just for T=i32
-#[derive(Default, Hash, Copy, Clone, Debug)]
+#[derive(Default, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector<T>(pub T, pub T);
 
 impl<T> Vector<T>
@@ -65,6 +67,15 @@ where U: PartialEq<T> {
 impl<T> Eq for Vector<T>
 where T: Eq {}
 
+impl<T> Hash for Vector<T>
+where T: Hash {
+	/// Hashes both coordinates, consistently with `PartialEq`.
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.0.hash(state);
+		self.1.hash(state);
+	}
+}
+
 impl<T, U> PartialOrd<Vector<T>> for Vector<U>
 where U: PartialOrd<T> {
 	/// Partially compares two vectors.
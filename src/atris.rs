@@ -1,24 +1,59 @@
+#![allow(clippy::module_inception)]
+
 pub mod algebra;
 pub mod block;
 pub mod piece;
 pub mod grid;
 pub mod gamemode;
+pub mod rotationsystem;
+pub mod randomizer;
+pub mod scoringrule;
+pub mod physics;
+pub mod level;
+pub mod attack;
+pub mod garbage;
+pub mod targeting;
+pub mod piecequeue;
+pub mod input;
+pub mod event;
+pub mod clock;
+pub mod replay;
+#[cfg(feature = "serde")]
+pub mod snapshot;
+pub mod gridengine;
 pub mod gridsession;
 pub mod atrissession;
 
 pub mod blocktypes;
 pub mod gamemodes;
+pub mod rotationsystems;
+pub mod randomizers;
+pub mod scoringrules;
 
-use rand::{rngs::ThreadRng, thread_rng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 pub struct Atris {
-    pub rng: ThreadRng,
+    seed: u64,
+    pub rng: StdRng,
 }
 
 impl Atris {
+    /// Creates a new `Atris` object with a random seed.
     pub fn new() -> Self {
+        Self::with_seed(StdRng::from_entropy().gen())
+    }
+
+    /// Creates a new `Atris` object whose random number generator is seeded with `seed`.
+    pub fn with_seed(seed: u64) -> Self {
         Self {
-            rng: thread_rng(),
+            seed,
+            rng: StdRng::seed_from_u64(seed),
         }
     }
+
+    pub fn seed(&self) -> u64 { self.seed }
+}
+
+impl Default for Atris {
+    fn default() -> Self { Self::new() }
 }
\ No newline at end of file
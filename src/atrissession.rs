@@ -1,16 +1,21 @@
-use std::{collections::HashMap, thread::{Thread, JoinHandle}, rc::Rc};
+use std::{collections::BTreeMap, sync::Arc};
 
 use crossbeam::channel::{Receiver, unbounded, Sender};
 use futures::executor::ThreadPool;
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use uuid::Uuid;
 
-use crate::{gridsession::GridSession, algebra::UVector, gamemode::GameMode, block::state::State};
+use crate::{gridsession::{GridSession, GridConfig, GridSeeds}, algebra::UVector, gamemode::GameMode, block::state::State};
+use crate::{event::{SessionEvent, GridEvent}, input::{HandlingConfig, PlayerInput}};
+use crate::{targeting::{Targeting, TargetingStrategy}, clock::{Clock, RealClock}, replay::{GridReplay, Replay}};
 
 #[derive(Debug)]
 pub struct Player {
     id: usize,
     active: bool,
+    grid: Option<usize>,
+    team: Option<usize>,
+    handling: HandlingConfig,
 }
 
 impl Player {
@@ -18,8 +23,31 @@ impl Player {
         Self {
             id: player_id,
             active: true,
+            grid: None,
+            team: None,
+            handling: HandlingConfig::default(),
         }
     }
+
+    pub fn id(&self) -> usize { self.id }
+
+    pub fn is_active(&self) -> bool { self.active }
+
+    /// Returns the id of the grid the player plays in, if any.
+    pub fn grid(&self) -> Option<usize> { self.grid }
+
+    /// Returns the team of the player, if any.
+    pub fn team(&self) -> Option<usize> { self.team }
+
+    /// Returns how the inputs of the player are handled.
+    pub fn handling(&self) -> &HandlingConfig { &self.handling }
+}
+
+/// Channels to communicate with a `GridSession`.
+#[derive(Debug)]
+struct GridChannels {
+    sender: Sender<SessionEvent>,
+    receiver: Receiver<GridEvent>,
 }
 
 #[derive(Debug)]
@@ -27,36 +55,147 @@ pub struct AtrisSession {
     uuid: Uuid,
     players: Vec<Player>,
     // Configurations:
-    game_mode: Box<dyn GameMode<StdRng>>,
-    tps: u64, // Ticks per Second, for the loop
+    game_mode: Arc<dyn GameMode<StdRng>>,
+    grid_config: GridConfig,
+    /// Whether all the grids get the same sequence of pieces.
+    same_pieces: bool,
+    /// Clock the grids created follow.
+    clock: Box<dyn Clock>,
+    /// Whether the grids created are recorded.
+    recording: bool,
     // State:
     state: State<u64>,
+    seed: u64,
     rng: StdRng,
+    /// Number of grids created so far, to derive the seeds of the next one.
+    n_created_grids: u64,
     // Thread pool and events channels:
     thread_pool: ThreadPool,
-    grids: HashMap<usize, Receiver<()>>,
-    sender: Sender<()>,
-    receiver: Receiver<()>,
+    grids: BTreeMap<usize, GridChannels>,
+    targeting: Targeting,
+    /// Whether the match is over, with only one team (or grid without a team) left.
+    match_over: bool,
+    /// Recordings sent by the grids whose game loop stopped.
+    replays: Vec<GridReplay>,
 }
 
 impl AtrisSession {
+    /// Creates a session with a random seed.
     pub fn new(game_mode: Box<dyn GameMode<StdRng>>, thread_pool: ThreadPool) -> Self {
-        let (sender, receiver) = unbounded();
+        Self::with_seed(game_mode, thread_pool, StdRng::from_entropy().gen())
+    }
+
+    /// Creates a session whose grids, and all their random events, are derived from `seed`. Sessions created with the
+    /// same seed, where the same things happen in the same order, play the same.
+    pub fn with_seed(game_mode: Box<dyn GameMode<StdRng>>, thread_pool: ThreadPool, seed: u64) -> Self {
         Self {
             uuid: Uuid::new_v4(),
             players: Vec::new(),
-            game_mode: game_mode,
-            tps: 30,
+            game_mode: Arc::from(game_mode),
+            grid_config: GridConfig::default(),
+            same_pieces: false,
+            clock: Box::new(RealClock::new()),
+            recording: false,
             state: State::new(),
-            rng: StdRng::from_entropy(), //thread_rng(),
-            thread_pool: thread_pool,
-            grids: HashMap::new(),
-            sender: sender,
-            receiver: receiver,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            n_created_grids: 0,
+            thread_pool,
+            grids: BTreeMap::new(),
+            targeting: Targeting::with_seed(derive_seed(seed, 1)),
+            match_over: false,
+            replays: Vec::new(),
         }
     }
 
-    pub fn create_grid(&mut self, grid_bounds: UVector) {
+    pub fn uuid(&self) -> Uuid { self.uuid }
+
+    pub fn game_mode(&self) -> &dyn GameMode<StdRng> { self.game_mode.as_ref() }
+
+    pub fn state(&self) -> State<u64> { self.state }
+
+    /// Returns the seed everything random in the session is derived from.
+    pub fn seed(&self) -> u64 { self.seed }
+
+    pub fn players(&self) -> &Vec<Player> { &self.players }
+
+    /// Returns the configuration of the grids created from now on.
+    pub fn grid_config(&self) -> &GridConfig { &self.grid_config }
+
+    /// Sets the configuration of the grids created from now on.
+    pub fn set_grid_config(&mut self, grid_config: GridConfig) {
+        self.grid_config = grid_config;
+    }
+
+    /// Checks whether all the grids get the same sequence of pieces.
+    pub fn same_pieces(&self) -> bool { self.same_pieces }
+
+    /// Sets whether the grids created from now on get the same sequence of pieces, as fair versus matches require.
+    pub fn set_same_pieces(&mut self, same_pieces: bool) {
+        self.same_pieces = same_pieces;
+    }
+
+    /// Sets the clock the grids created from now on follow, instead of the wall clock.
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Checks whether the grids created from now on are recorded.
+    pub fn is_recording(&self) -> bool { self.recording }
+
+    /// Sets whether the grids created from now on are recorded. Their recordings are collected by `poll_events` once
+    /// their game loop stops, and put together by `replay`.
+    pub fn set_recording(&mut self, recording: bool) {
+        self.recording = recording;
+    }
+
+    /// Returns the recordings of the grids collected so far, in the order of their ids, with the seed of the session.
+    /// Each recording keeps the configuration its grid was created with.
+    pub fn replay(&self) -> Replay {
+        let mut grids = self.replays.clone();
+        grids.sort_by_key(|grid| grid.id());
+        Replay { seed: self.seed, grids }
+    }
+
+    /// Returns who targets who among the grids, and their KOs.
+    pub fn targeting(&self) -> &Targeting { &self.targeting }
+
+    /// Changes how a grid picks the opponents its garbage is sent to.
+    pub fn set_targeting_strategy(&mut self, grid_id: usize, strategy: TargetingStrategy) {
+        self.targeting.set_strategy(grid_id, strategy);
+    }
+
+    /// Sets whether the badges earned by knocking out opponents multiply the attack of the grids.
+    pub fn set_badges(&mut self, badges: bool) {
+        self.targeting.set_badges(badges);
+    }
+
+    /// Sets whether the garbage sent to a grid is split across its teammates alive.
+    pub fn set_split_garbage(&mut self, split: bool) {
+        self.targeting.set_split(split);
+    }
+
+    /// Checks whether the match is over: only one team, or one grid without a team, has grids still alive.
+    pub fn is_match_over(&self) -> bool { self.match_over }
+
+    /// Returns the ids of the grids still alive, the winners once the match is over.
+    pub fn grids_alive(&self) -> Vec<usize> { self.targeting.alive().collect() }
+
+    /// Returns the seeds of the next grid created: the `n`-th grid gets the streams `2n + 2` (pieces) and `2n + 3`
+    /// (anything else) of the session seed, or the stream 0 for the pieces if all the grids get the same ones. The
+    /// stream 1 is the one of the targeting.
+    fn next_grid_seeds(&mut self) -> GridSeeds {
+        let n = self.n_created_grids;
+        self.n_created_grids += 1;
+        GridSeeds {
+            pieces: derive_seed(self.seed, if self.same_pieces { 0 } else { 2 * n + 2 }),
+            grid: derive_seed(self.seed, 2 * n + 3),
+        }
+    }
+
+    /// Creates a new grid, with its own `GridSession` running in the thread pool, and returns its id. The other grids
+    /// are notified with a `SessionEvent::GridAdded` message.
+    pub fn create_grid(&mut self, grid_bounds: UVector) -> usize {
         let mut grid_session_id: usize;
         loop {
             grid_session_id = self.rng.gen_range(1..100);
@@ -65,36 +204,177 @@ impl AtrisSession {
             }
         }
         let (grid_sender, grid_receiver) = unbounded(); // For grid events
-        let game_receiver = self.receiver.clone();
-        self.grids.insert(grid_session_id, grid_receiver);
-        let tps = self.tps; // And other configuration (make struct)
+        let (game_sender, game_receiver) = unbounded(); // For session events
+        for (&other_id, other) in self.grids.iter() {
+            let _ = other.sender.send(SessionEvent::GridAdded(grid_session_id));
+            let _ = game_sender.send(SessionEvent::GridAdded(other_id));
+        }
+        self.grids.insert(grid_session_id, GridChannels { sender: game_sender, receiver: grid_receiver });
+        self.targeting.add(grid_session_id);
+        let game_mode = Arc::clone(&self.game_mode);
+        let grid_config = self.grid_config.clone();
+        let seeds = self.next_grid_seeds();
+        let clock = self.clock.clone_box();
+        let recording = self.recording;
         self.thread_pool.spawn_ok(async move {
-            let mut grid_session = GridSession::new(grid_session_id, grid_bounds, grid_sender, game_receiver, tps);
+            let mut grid_session = GridSession::new(grid_session_id, grid_bounds, game_mode.as_ref(), grid_config,
+                seeds, grid_sender, game_receiver);
+            grid_session.set_clock(clock);
+            grid_session.set_recording(recording);
             grid_session.await_until_start();
-            // grid_session.start(); // Wait until start event (make enum)
         });
-        self.sender.send(()); // Send GridAdded(Receiver)
-        // let tps = self.tps;
-        // let block_types = &self.block_types;
-        // self.grids.insert(grid_session_id, thread);
+        grid_session_id
     }
 
-    pub fn create_player(&mut self) {
+    /// Stops and removes a grid. The other grids are notified with a `SessionEvent::GridRemoved` message.
+    pub fn remove_grid(&mut self, grid_id: usize) {
+        self.targeting.remove(grid_id);
+        if let Some(grid) = self.grids.remove(&grid_id) {
+            let _ = grid.sender.send(SessionEvent::Stop);
+            self.broadcast(SessionEvent::GridRemoved(grid_id));
+        }
+        for player in self.players.iter_mut().filter(|player| player.grid == Some(grid_id)) {
+            player.grid = None;
+        }
+    }
+
+    /// Creates a new player, and returns its id.
+    pub fn create_player(&mut self) -> usize {
         let player_id = self.players.len();
         self.players.push(Player::new(player_id));
-        // self.grids.create_figure(player_id);
+        player_id
     }
 
-    pub fn start(&mut self) {
-        // let grid_session = self.grids.remove(&3).unwrap();
-        // self.thread_pool.spawn_ok(async move { grid_session.start(); });
-        for grid_session in self.grids.values_mut() {
-            // let thread = thread::spawn(|| {
-                // self.thread_pool.spawn_ok(async {
-                //     grid_session.start();
-                // });
-                // self.grids[&grid_session_id].run()
-            // });
+    /// Makes a player play in a grid.
+    pub fn assign_player(&mut self, player_id: usize, grid_id: usize) {
+        let (Some(player), Some(grid)) = (self.players.get_mut(player_id), self.grids.get(&grid_id)) else {
+            return;
+        };
+        player.grid = Some(grid_id);
+        let _ = grid.sender.send(SessionEvent::PlayerAdded(player_id, player.handling));
+        self.targeting.set_team(grid_id, player.team);
+    }
+
+    /// Puts a player in a team, or in none. The grid the player plays in joins the team too: its garbage only goes to
+    /// the grids of other teams.
+    pub fn set_team(&mut self, player_id: usize, team: Option<usize>) {
+        let Some(player) = self.players.get_mut(player_id) else {
+            return;
+        };
+        player.team = team;
+        if let Some(grid_id) = player.grid {
+            self.targeting.set_team(grid_id, team);
         }
     }
-}
\ No newline at end of file
+
+    /// Changes how the inputs of a player are handled (DAS, ARR and soft drop factor).
+    pub fn set_handling(&mut self, player_id: usize, handling: HandlingConfig) {
+        let Some(player) = self.players.get_mut(player_id) else {
+            return;
+        };
+        player.handling = handling;
+        if let Some(grid_id) = player.grid {
+            self.send(grid_id, SessionEvent::Handling(player_id, handling));
+        }
+    }
+
+    /// Sends an input of a player to the grid they play in, where it's handled on the next tick.
+    pub fn input(&self, player_id: usize, input: PlayerInput) {
+        let Some(grid_id) = self.players.get(player_id).and_then(|player| player.grid) else {
+            return;
+        };
+        self.send(grid_id, SessionEvent::Input(player_id, input));
+    }
+
+    /// Sends a message to a grid.
+    pub fn send(&self, grid_id: usize, e: SessionEvent) {
+        if let Some(grid) = self.grids.get(&grid_id) {
+            let _ = grid.sender.send(e);
+        }
+    }
+
+    /// Sends a message to all the grids.
+    pub fn broadcast(&self, e: SessionEvent) {
+        for grid in self.grids.values() {
+            let _ = grid.sender.send(e.clone());
+        }
+    }
+
+    /// Returns all the messages received from the grids so far, with the ids of the grids that sent them, in the order
+    /// of their ids. The garbage sent by a grid is routed to the opponents it targets, and the grids whose game is over
+    /// are knocked out. Once only one team is left, the match is over and the session stops. The recordings of the
+    /// grids are kept for `replay`.
+    pub fn poll_events(&mut self) -> Vec<(usize, GridEvent)> {
+        let mut events = Vec::new();
+        for (&grid_id, grid) in self.grids.iter() {
+            events.extend(grid.receiver.try_iter().map(|e| (grid_id, e)));
+        }
+        for (grid_id, e) in events.iter() {
+            match e {
+                &GridEvent::Height(height) => self.targeting.set_height(*grid_id, height),
+                &GridEvent::Garbage(lines) => {
+                    for (target, lines) in self.targeting.attack(*grid_id, lines) {
+                        self.send(target, SessionEvent::Garbage(lines));
+                    }
+                },
+                GridEvent::GameOver(_) => {
+                    self.targeting.knock_out(*grid_id);
+                    if !self.match_over && self.targeting.sides_alive() <= 1 {
+                        self.match_over = true;
+                        self.stop();
+                    }
+                },
+                GridEvent::Replay(replay) => self.replays.push(replay.as_ref().clone()),
+                _ => (),
+            }
+        }
+        events
+    }
+
+    pub fn start(&mut self) {
+        self.broadcast(SessionEvent::Start);
+    }
+
+    pub fn pause(&mut self) {
+        self.broadcast(SessionEvent::Pause);
+    }
+
+    pub fn resume(&mut self) {
+        self.broadcast(SessionEvent::Resume);
+    }
+
+    pub fn stop(&mut self) {
+        self.broadcast(SessionEvent::Stop);
+    }
+
+    /// Makes all the grids run faster or slower, in percent of their normal speed.
+    pub fn set_speed(&mut self, speed: u32) {
+        self.broadcast(SessionEvent::Speed(speed));
+    }
+
+    /// Starts or stops frame stepping in all the grids, where they only advance one tick at a time with `step_frame`.
+    pub fn set_frame_stepping(&mut self, frame_stepping: bool) {
+        self.broadcast(SessionEvent::FrameStepping(frame_stepping));
+    }
+
+    /// Advances all the grids one tick while frame stepping.
+    pub fn step_frame(&mut self) {
+        self.broadcast(SessionEvent::StepFrame);
+    }
+}
+
+/// Derives the seed of a stream from a seed, so that different streams of the same seed are unrelated (SplitMix64).
+///
+/// ```
+/// use atris::atrissession::derive_seed;
+///
+/// assert_eq!(derive_seed(42, 1), derive_seed(42, 1));
+/// assert_ne!(derive_seed(42, 1), derive_seed(42, 2));
+/// assert_ne!(derive_seed(42, 1), derive_seed(43, 1));
+/// ```
+pub fn derive_seed(seed: u64, stream: u64) -> u64 {
+    let mut z = seed.wrapping_add(stream.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,123 @@
+use crate::{grid::Spin, scoringrule::Lock};
+
+/// Lines of garbage sent for clearing rows, indexed by the number of rows cleared at once. Past the end of a table, the
+/// last value is used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttackTable {
+    /// Lines sent for clears without a spin.
+    pub lines: Vec<u32>,
+    /// Lines sent for clears with a full spin.
+    pub spin: Vec<u32>,
+    /// Lines sent for clears with a mini spin.
+    pub mini_spin: Vec<u32>,
+    /// Lines added by a combo, indexed by the number of consecutive clears before this one.
+    pub combo: Vec<u32>,
+    /// Lines added by a difficult clear (see `Lock::is_difficult`) right after another one.
+    pub back_to_back: u32,
+    /// Lines added by a clear that leaves the grid empty.
+    pub perfect_clear: u32,
+}
+
+impl AttackTable {
+    /// The attack table of the guideline multiplayer games.
+    pub fn guideline() -> Self {
+        Self {
+            lines: vec!(0, 0, 1, 2, 4),
+            spin: vec!(0, 2, 4, 6),
+            mini_spin: vec!(0, 0, 1),
+            combo: vec!(0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5),
+            back_to_back: 1,
+            perfect_clear: 10,
+        }
+    }
+
+    /// Returns the value of `table` for `i`, or its last value past its end.
+    fn get(table: &[u32], i: usize) -> u32 {
+        table.get(i).or(table.last()).copied().unwrap_or(0)
+    }
+}
+
+impl Default for AttackTable {
+    fn default() -> Self { Self::guideline() }
+}
+
+/// The attack of a grid: follows an `AttackTable`, keeping track of its combo and back-to-back. Difficult clears take 4
+/// rows or more by default, and grids use the minimum of their `ScoringRule`.
+///
+/// ```
+/// use atris::attack::{Attack, AttackTable};
+/// use atris::grid::Spin;
+/// use atris::scoringrule::Lock;
+///
+/// let mut attack = Attack::new(AttackTable::guideline());
+/// assert_eq!(attack.on_lock(&Lock { lines: 4, ..Default::default() }), 4);
+/// assert_eq!(attack.on_lock(&Lock { lines: 2, spin: Spin::Full, ..Default::default() }), 5); // B2B
+/// assert_eq!(attack.on_lock(&Lock { lines: 1, ..Default::default() }), 1); // Combo of 2
+/// assert_eq!(attack.on_lock(&Lock { lines: 0, ..Default::default() }), 0);
+/// assert_eq!(attack.combo(), None);
+/// assert_eq!(attack.on_lock(&Lock { lines: 4, perfect_clear: true, ..Default::default() }), 14);
+/// attack.set_difficult_lines(5);
+/// assert_eq!(attack.on_lock(&Lock { lines: 4, ..Default::default() }), 4); // Not difficult, so no B2B
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Attack {
+    table: AttackTable,
+    combo: Option<u32>,
+    back_to_back: bool,
+    /// Minimum number of rows of a difficult clear.
+    difficult_lines: usize,
+}
+
+impl Attack {
+    pub fn new(table: AttackTable) -> Self {
+        Self {
+            table,
+            combo: None,
+            back_to_back: false,
+            difficult_lines: 4,
+        }
+    }
+
+    pub fn table(&self) -> &AttackTable { &self.table }
+
+    pub fn difficult_lines(&self) -> usize { self.difficult_lines }
+
+    /// Sets the minimum number of rows of a difficult clear (4 by default).
+    pub fn set_difficult_lines(&mut self, difficult_lines: usize) {
+        self.difficult_lines = difficult_lines;
+    }
+
+    /// Returns the number of consecutive clears after the first one, if the last `Figure` cleared rows.
+    pub fn combo(&self) -> Option<u32> { self.combo }
+
+    /// Checks whether the next difficult clear is a back-to-back.
+    pub fn back_to_back(&self) -> bool { self.back_to_back }
+
+    /// Returns the lines of garbage sent by a `Figure` locking, and updates the combo and back-to-back.
+    pub fn on_lock(&mut self, lock: &Lock) -> u32 {
+        if lock.lines == 0 {
+            self.combo = None;
+            return 0;
+        }
+        let combo = self.combo.map_or(0, |combo| combo + 1);
+        self.combo = Some(combo);
+        let table = &self.table;
+        let mut lines = match lock.spin {
+            Spin::None => AttackTable::get(&table.lines, lock.lines),
+            Spin::Mini => AttackTable::get(&table.mini_spin, lock.lines),
+            Spin::Full => AttackTable::get(&table.spin, lock.lines),
+        };
+        let difficult = lock.is_difficult(self.difficult_lines);
+        if difficult && self.back_to_back {
+            lines += table.back_to_back;
+        }
+        self.back_to_back = difficult;
+        lines += AttackTable::get(&table.combo, combo as usize);
+        if lock.perfect_clear {
+            lines += table.perfect_clear;
+        }
+        lines
+    }
+}
@@ -8,6 +8,8 @@ pub use super::state::{State, BitRange};
 pub use super::blocktype::BlockType;
 
 use crate::algebra::UVector;
+#[cfg(feature = "serde")]
+use crate::{blocktypes::BlockTypes, snapshot::{deserialize_resolved, DeserializeWith, Unresolved}};
 
 // pub type Cell = Option<Block>;
 
@@ -32,6 +34,45 @@ impl<'bt> Block<'bt> {
     }
 }
 
+/// A `Block` is serialized with the `id` of its `BlockType`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Block<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut block = serializer.serialize_struct("Block", 2)?;
+        block.serialize_field("block_type", self.block_type.id())?;
+        block.serialize_field("state", &self.state)?;
+        block.end()
+    }
+}
+
+/// Serialized form of a `Block`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+pub(crate) struct BlockData {
+    block_type: String,
+    state: State<u64>,
+}
+
+#[cfg(feature = "serde")]
+impl<'bt> Unresolved<'bt> for BlockData {
+    type Resolved = Block<'bt>;
+
+    fn resolve(self, block_types: &'bt BlockTypes) -> Result<Block<'bt>, String> {
+        let block_type = block_types.get(&self.block_type)
+            .ok_or_else(|| format!("unknown block type {:?}", self.block_type))?;
+        Ok(Block { block_type, state: self.state })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'bt> DeserializeWith<'bt> for Block<'bt> {
+    fn deserialize_with<'de, D: serde::Deserializer<'de>>(deserializer: D, block_types: &'bt BlockTypes)
+            -> Result<Self, D::Error> {
+        deserialize_resolved::<BlockData, D>(deserializer, block_types)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct PositionedBlock<'bt> {
     pub pos: UVector,
@@ -50,7 +91,7 @@ impl<'bt> Eq for PositionedBlock<'bt> {}
 impl<'bt> PartialOrd for PositionedBlock<'bt> {
 	/// Partially compares two `BlockPos`, comparing just their positions.
 	fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> {
-        self.pos.partial_cmp(&rhs.pos)
+        Some(self.cmp(rhs))
 	}
 }
 
@@ -5,7 +5,7 @@ use core::fmt::Debug;
 // use super::Block;
 
 /// This `trait` represents the behaviour of every `Block`, depending on the `Block`'s type and state (and global state).
-pub trait BlockType: Sync + Debug {
+pub trait BlockType: Send + Sync + Debug {
     // const NAME: str;
     /// Returns the identifier (an unique name) of the `BlockType` object. The value returned must be constant all the time.
     fn id(&self) -> &'static str;
@@ -4,4 +4,6 @@ pub mod blocktype;
 pub mod state;
 
 pub use block::{Block, PositionedBlock};
-pub use blocktype::BlockType;
\ No newline at end of file
+pub use blocktype::BlockType;
+#[cfg(feature = "serde")]
+pub(crate) use block::BlockData;
\ No newline at end of file
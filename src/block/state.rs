@@ -3,6 +3,7 @@ use std::ops::{Sub, Shl, Shr, BitAnd, BitOr, Not};
 /// Represents a generic state that is stored as an unsigned integer. Values can be extrated and set with methods `get` and `set`.
 /// Operations without a `State` objects can be performed with functions `get_from` and `set_to`.
 #[derive(Default, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct State<T: num::Unsigned> {
     value: T,
 }
@@ -127,6 +128,7 @@ impl<T, U> From<T> for State<U>
 /// This struct represents a range (position of the first bit, length of range) of bits in an unsigned integer. This is
 /// used for `State`'s operations.
 #[derive(Default, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BitRange {
     pub pos: u8,
     pub len: u8,
@@ -31,7 +31,11 @@ impl BlockTypes {
     /// Adds a `BlockType` object to the list, using its `id()` method as key. If another one with the same name already
     /// exists in the map, the new one replaces it.
     /// The method returns `Some<bt>` if some `bt` object existed before and is being replaced. Otherwise, it returns `None`.
-    fn add<T: BlockType + 'static>(&mut self, bt: T) -> Option<Box<dyn BlockType>> {
+    pub fn add<T: BlockType + 'static>(&mut self, bt: T) -> Option<Box<dyn BlockType>> {
         self.map.insert(bt.id(), Box::new(bt))
     }
-}
\ No newline at end of file
+}
+
+impl Default for BlockTypes {
+    fn default() -> Self { Self::new() }
+}
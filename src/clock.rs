@@ -0,0 +1,233 @@
+use std::fmt::Debug;
+use std::sync::{Arc, atomic::{AtomicU64, Ordering}};
+use std::time::{Duration, Instant};
+
+/// This `trait` represents where the time of a game loop comes from.
+pub trait Clock: Send + Sync + Debug {
+    /// Returns the time elapsed since the clock was created.
+    fn now(&self) -> Duration;
+
+    /// Waits until the clock reaches `time`. Returns right away if it's already past it.
+    fn sleep_until(&mut self, time: Duration);
+
+    /// Returns a new `Clock` of the same kind. Clocks that can be controlled share their time with their clones.
+    fn clone_box(&self) -> Box<dyn Clock>;
+}
+
+/// The wall clock: time passes on its own, and sleeping blocks the thread.
+#[derive(Copy, Clone, Debug)]
+pub struct RealClock {
+    start: Instant,
+}
+
+impl RealClock {
+    pub fn new() -> Self {
+        Self { start: Instant::now() }
+    }
+}
+
+impl Default for RealClock {
+    fn default() -> Self { Self::new() }
+}
+
+impl Clock for RealClock {
+    fn now(&self) -> Duration { self.start.elapsed() }
+
+    fn sleep_until(&mut self, time: Duration) {
+        let now = self.now();
+        if time > now {
+            std::thread::sleep(time - now);
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Clock> { Box::new(*self) }
+}
+
+/// A clock whose time only moves when told to: sleeping makes it jump to the time waited for, right away. Its clones
+/// share its time, so a test can hold one while a game loop uses another.
+///
+/// ```
+/// use std::time::Duration;
+/// use atris::clock::{Clock, ManualClock};
+///
+/// let clock = ManualClock::new();
+/// let mut other = clock.clone();
+/// clock.advance(Duration::from_millis(10));
+/// assert_eq!(other.now(), Duration::from_millis(10));
+/// other.sleep_until(Duration::from_millis(25));
+/// assert_eq!(clock.now(), Duration::from_millis(25));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock {
+    /// Time in nanoseconds.
+    time: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self { time: Arc::new(AtomicU64::new(0)) }
+    }
+
+    /// Moves the time forward.
+    pub fn advance(&self, duration: Duration) {
+        self.time.fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    /// Sets the time, even backwards.
+    pub fn set(&self, time: Duration) {
+        self.time.store(time.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration { Duration::from_nanos(self.time.load(Ordering::SeqCst)) }
+
+    fn sleep_until(&mut self, time: Duration) {
+        self.time.fetch_max(time.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    fn clone_box(&self) -> Box<dyn Clock> { Box::new(self.clone()) }
+}
+
+/// Decides when the ticks of a game loop run, following a `Clock`. Ticks are scheduled from a fixed origin, so when
+/// some run late, the next calls catch up with all of them (up to a limit) instead of drifting. The loop can run
+/// faster or slower than its ticks per second, or only advance one tick at a time when frame stepping.
+///
+/// ```
+/// use std::time::Duration;
+/// use atris::clock::{Clock, ManualClock, Ticker};
+///
+/// let clock = ManualClock::new();
+/// let mut ticker = Ticker::new(Box::new(clock.clone()), 10);
+/// assert_eq!(ticker.due(), 0);
+/// clock.advance(Duration::from_millis(350)); // Late by 3 ticks and a half
+/// assert_eq!(ticker.due(), 3);
+/// ticker.wait(); // Until the 4th tick
+/// assert_eq!(clock.now(), Duration::from_millis(400));
+/// assert_eq!(ticker.due(), 1);
+/// ticker.set_speed(50); // Half speed: a tick every 200 ms
+/// clock.advance(Duration::from_millis(300));
+/// assert_eq!(ticker.due(), 1);
+/// ticker.set_frame_stepping(true);
+/// clock.advance(Duration::from_secs(10));
+/// assert_eq!(ticker.due(), 0);
+/// ticker.step_frame();
+/// assert_eq!(ticker.due(), 1);
+/// assert_eq!(ticker.ticks(), 6);
+/// ```
+#[derive(Debug)]
+pub struct Ticker {
+    clock: Box<dyn Clock>,
+    tps: u64,
+    /// Speed in percent of the normal one.
+    speed: u32,
+    /// Time and number of ticks the schedule counts from. They move when the speed or the frame stepping changes.
+    origin: (Duration, u64),
+    ticks: u64,
+    max_catch_up: u64,
+    frame_stepping: bool,
+    /// Frames requested while frame stepping and not run yet.
+    frames: u64,
+}
+
+impl Ticker {
+    /// Creates a ticker running `tps` ticks per second of `clock`, at normal speed, catching up to a second of ticks.
+    pub fn new(clock: Box<dyn Clock>, tps: u64) -> Self {
+        let now = clock.now();
+        Self {
+            clock,
+            tps: tps.max(1),
+            speed: 100,
+            origin: (now, 0),
+            ticks: 0,
+            max_catch_up: tps.max(1),
+            frame_stepping: false,
+            frames: 0,
+        }
+    }
+
+    /// Returns the number of ticks run so far.
+    pub fn ticks(&self) -> u64 { self.ticks }
+
+    /// Returns the speed, in percent of the normal one.
+    pub fn speed(&self) -> u32 { self.speed }
+
+    /// Changes the speed, in percent of the normal one: 200 runs twice as many ticks per second, 50 half as many.
+    pub fn set_speed(&mut self, speed: u32) {
+        self.restart_schedule();
+        self.speed = speed.max(1);
+    }
+
+    /// Returns the maximum number of late ticks run at once. Ticks later than that are skipped.
+    pub fn max_catch_up(&self) -> u64 { self.max_catch_up }
+
+    pub fn set_max_catch_up(&mut self, max_catch_up: u64) {
+        self.max_catch_up = max_catch_up.max(1);
+    }
+
+    /// Checks whether ticks only run when requested with `step_frame`.
+    pub fn is_frame_stepping(&self) -> bool { self.frame_stepping }
+
+    /// Starts or stops frame stepping, where ticks only run when requested with `step_frame`.
+    pub fn set_frame_stepping(&mut self, frame_stepping: bool) {
+        self.frame_stepping = frame_stepping;
+        self.frames = 0;
+        self.restart_schedule();
+    }
+
+    /// Requests one more tick while frame stepping.
+    pub fn step_frame(&mut self) {
+        if self.frame_stepping {
+            self.frames += 1;
+        }
+    }
+
+    /// Makes the schedule count from now, so the time before doesn't have to be caught up.
+    pub fn restart_schedule(&mut self) {
+        self.origin = (self.clock.now(), self.ticks);
+    }
+
+    /// Returns the number of ticks of the schedule that should have run by `time`.
+    fn scheduled(&self, time: Duration) -> u64 {
+        let elapsed = time.saturating_sub(self.origin.0).as_nanos();
+        let ticks = elapsed * self.tps as u128 * self.speed as u128 / (100 * 1_000_000_000);
+        self.origin.1 + ticks as u64
+    }
+
+    /// Returns the time the given tick of the schedule is due.
+    fn due_time(&self, tick: u64) -> Duration {
+        let ticks = (tick - self.origin.1) as u128;
+        let rate = self.tps as u128 * self.speed as u128;
+        let nanos = (ticks * 100 * 1_000_000_000).div_ceil(rate);
+        self.origin.0 + Duration::from_nanos(nanos as u64)
+    }
+
+    /// Returns the number of ticks to run now, counting them as run. Ticks later than the maximum catch up are skipped.
+    pub fn due(&mut self) -> u64 {
+        if self.frame_stepping {
+            self.ticks += self.frames;
+            return std::mem::take(&mut self.frames);
+        }
+        let now = self.clock.now();
+        let mut due = self.scheduled(now).saturating_sub(self.ticks);
+        if due > self.max_catch_up {
+            due = self.max_catch_up;
+            self.ticks += due;
+            self.restart_schedule();
+        } else {
+            self.ticks += due;
+        }
+        due
+    }
+
+    /// Waits until the next tick is due. While frame stepping, waits for a tick at normal speed, so a loop checking for
+    /// requested frames doesn't keep the processor busy.
+    pub fn wait(&mut self) {
+        let time = if self.frame_stepping {
+            self.clock.now() + Duration::from_nanos(1_000_000_000 / self.tps)
+        } else {
+            self.due_time(self.ticks + 1)
+        };
+        self.clock.sleep_until(time);
+    }
+}
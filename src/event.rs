@@ -0,0 +1,74 @@
+use crate::input::{HandlingConfig, PlayerInput};
+use crate::piece::shape::Shape;
+use crate::scoringrule::Lock;
+use crate::replay::GridReplay;
+
+/// Messages sent by an `AtrisSession` to each of its `GridSession`s.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SessionEvent {
+    /// Starts the game loop of the grid.
+    Start,
+    /// Pauses the game loop of the grid, that keeps receiving messages.
+    Pause,
+    /// Resumes the game loop of the grid after a pause.
+    Resume,
+    /// Stops the game loop of the grid for good.
+    Stop,
+    /// Adds a player to the grid, with the way their inputs are handled.
+    PlayerAdded(usize, HandlingConfig),
+    /// Changes the way the inputs of a player playing in the grid are handled.
+    Handling(usize, HandlingConfig),
+    /// An input of a player playing in the grid.
+    Input(usize, PlayerInput),
+    /// Another grid was added to the session.
+    GridAdded(usize),
+    /// Another grid was removed from the session.
+    GridRemoved(usize),
+    /// Lines of garbage the grid receives.
+    Garbage(u32),
+    /// Changes the speed of the game loop, in percent of the normal one.
+    Speed(u32),
+    /// Starts or stops frame stepping, where the game loop only advances when told to.
+    FrameStepping(bool),
+    /// Advances the game loop one tick while frame stepping.
+    StepFrame,
+}
+
+/// Messages sent by a `GridSession` to its `AtrisSession`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GridEvent {
+    /// A `Figure` locked. It's sent before the rows it filled are cleared.
+    Locked(Lock),
+    /// Some rows were cleared. They are in ascending order, as they were before being deleted.
+    LinesCleared(Vec<usize>),
+    /// Lines of garbage the grid sends to its opponents.
+    Garbage(u32),
+    /// The grid reached a new level.
+    Level(u32),
+    /// The height of the stack of the grid changed.
+    Height(usize),
+    /// A player put a piece in their hold slot: the shape of the piece they now hold.
+    Hold(usize, Shape),
+    /// The pieces that will spawn next changed: their shapes, in order.
+    Queue(Vec<Shape>),
+    /// The ghost of a player's `Figure` changed: the absolute positions of its blocks, or `None` without a `Figure`.
+    Ghost(usize, Option<Shape>),
+    /// The game is over for the grid, and its game loop stopped.
+    GameOver(GameOver),
+    /// The recording of the grid, sent when its game loop stops if it was recording.
+    Replay(Box<GridReplay>),
+}
+
+/// Why the game is over for a grid.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameOver {
+    /// A `Figure` spawned overlapping the blocks of the grid.
+    BlockOut,
+    /// A `Figure` locked fully above the visible area of the grid.
+    LockOut,
+    /// Garbage pushed blocks past the top of the grid.
+    TopOut,
+}
\ No newline at end of file
@@ -2,10 +2,24 @@ use std::fmt::Debug;
 
 use rand::Rng;
 
+use crate::level::LevelCurve;
 use crate::piece::Piece;
+use crate::randomizer::Randomizer;
+use crate::rotationsystem::RotationSystem;
+use crate::scoringrule::ScoringRule;
 
-pub trait GameMode<R: Rng>: Debug {
+pub trait GameMode<R: Rng>: Send + Sync + Debug {
     fn initialize(&mut self) {}
-    fn next_piece(&self, rng: &mut R) -> Piece;
+    /// Returns a new `Randomizer`, in its initial state, for a grid to pick its pieces with.
+    fn randomizer(&self) -> Box<dyn Randomizer>;
+    /// Returns the next piece of a grid, picked with the `Randomizer` of the grid.
+    fn next_piece(&self, randomizer: &mut dyn Randomizer, rng: &mut R) -> Piece<'_>;
+    /// Returns the `RotationSystem` that pieces follow in this game mode.
+    fn rotation_system(&self) -> &dyn RotationSystem;
+    /// Returns a new `ScoringRule`, in its initial state, for a grid to score its points with.
+    fn scoring_rule(&self) -> Box<dyn ScoringRule>;
+    /// Returns the `LevelCurve` giving the level and speed of the grids, if any. Without one, grids stay at level 1 and
+    /// keep the physics of their configuration.
+    fn level_curve(&self) -> Option<&LevelCurve> { None }
     // fn on_placed(&self);
 }
\ No newline at end of file
@@ -1,29 +1,117 @@
+use std::fmt;
+
 use rand::Rng;
 
 use crate::{gamemode::GameMode, piece::{shape::Shapes, Piece}, block::BlockType, blocktypes::standard::StandardType};
+use crate::{rotationsystem::RotationSystem, rotationsystems::SuperRotationSystem};
+use crate::{scoringrule::ScoringRule, scoringrules::GuidelineScoring, level::LevelCurve};
+use crate::{randomizer::Randomizer, randomizers::PureRandom};
 
+/// A game mode where pieces are picked among weighted configurations, each one giving a `BlockType` and a number of
+/// blocks. A configuration is picked with a probability proportional to its weight.
+///
+/// ```
+/// use atris::block::BlockType;
+/// use atris::blocktypes::{rock::RockType, standard::StandardType};
+/// use atris::gamemodes::custom::{CustomMode, PieceConfiguration, ConfigurationError};
+///
+/// let block_types: Vec<Box<dyn BlockType>> = vec!(Box::new(StandardType{}), Box::new(RockType{}));
+/// let configurations = vec!(PieceConfiguration::new(0, 4, 3), PieceConfiguration::new(1, 5, 1));
+/// let mode = CustomMode::new(block_types, configurations).unwrap();
+/// assert_eq!(mode.probabilities(), vec!(0.75, 0.25));
+///
+/// let block_types: Vec<Box<dyn BlockType>> = vec!(Box::new(StandardType{}));
+/// let configurations = vec!(PieceConfiguration::new(0, 4, 3), PieceConfiguration::new(1, 5, 1));
+/// assert_eq!(CustomMode::new(block_types, configurations).unwrap_err(), ConfigurationError::UnknownBlockType(1));
+/// ```
 #[derive(Debug)]
 pub struct CustomMode {
     shapes: Shapes,
     block_types: Vec<Box<dyn BlockType>>,
     configurations: Vec<PieceConfiguration>,
-    total_weight: u32,
+    total_weight: u64,
+    rotation_system: Box<dyn RotationSystem>,
+    randomizer: Box<dyn Randomizer>,
+    scoring_rule: Box<dyn ScoringRule>,
+    level_curve: Option<LevelCurve>,
 }
 
 impl CustomMode {
-    pub fn new(block_types: Vec<Box<dyn BlockType>>, configurations: Vec<PieceConfiguration>) -> Self {
-        Self {
-            shapes: Shapes::new(),
-            block_types: block_types,
-            configurations: configurations,
-            total_weight: 0,
+    /// Constructs a new `CustomMode`. Without block types, the standard block type is used, and without configurations,
+    /// there is one for each block type, with 4 blocks and the same weight. Fails if any configuration is invalid.
+    pub fn new(mut block_types: Vec<Box<dyn BlockType>>, mut configurations: Vec<PieceConfiguration>)
+            -> Result<Self, ConfigurationError> {
+        if block_types.is_empty() {
+            block_types.push(Box::new(StandardType{}));
+        }
+        if configurations.is_empty() {
+            configurations = (0..block_types.len()).map(|bt| PieceConfiguration::new(bt, 4, 1)).collect();
         }
+        for (i, conf) in configurations.iter().enumerate() {
+            if conf.block_type >= block_types.len() {
+                return Err(ConfigurationError::UnknownBlockType(i));
+            }
+            if conf.n_blocks == 0 {
+                return Err(ConfigurationError::NoBlocks(i));
+            }
+            if conf.weight == 0 {
+                return Err(ConfigurationError::NoWeight(i));
+            }
+        }
+        let total_weight = configurations.iter().map(|conf| conf.weight as u64).sum();
+        Ok(Self {
+            shapes: Shapes::new(),
+            block_types,
+            configurations,
+            total_weight,
+            rotation_system: Box::new(SuperRotationSystem::new()),
+            randomizer: Box::new(PureRandom{}),
+            scoring_rule: Box::new(GuidelineScoring::new()),
+            level_curve: Some(LevelCurve::guideline()),
+        })
     }
-    
+
+    /// Replaces the `RotationSystem` used (SRS by default).
+    pub fn set_rotation_system(&mut self, rotation_system: Box<dyn RotationSystem>) {
+        self.rotation_system = rotation_system;
+    }
+
+    /// Replaces the `Randomizer` used to pick the shape of the pieces (pure random by default), once their number of
+    /// blocks is chosen. It picks among the shapes given by `Shapes::shapes(n_blocks)`.
+    pub fn set_randomizer(&mut self, randomizer: Box<dyn Randomizer>) {
+        self.randomizer = randomizer;
+    }
+
+    /// Replaces the `ScoringRule` used (guideline by default).
+    pub fn set_scoring_rule(&mut self, scoring_rule: Box<dyn ScoringRule>) {
+        self.scoring_rule = scoring_rule;
+    }
+
+    /// Replaces the `LevelCurve` used (guideline by default), or removes it with `None` so that grids keep the physics
+    /// of their configuration.
+    pub fn set_level_curve(&mut self, level_curve: Option<LevelCurve>) {
+        self.level_curve = level_curve;
+    }
+
+    pub fn configurations(&self) -> &[PieceConfiguration] { &self.configurations }
+
+    /// Returns the probability of the configuration at `index` to be picked for the next piece, if it exists.
+    pub fn probability(&self, index: usize) -> Option<f64> {
+        Some(self.configurations.get(index)?.weight as f64 / self.total_weight as f64)
+    }
+
+    /// Returns the probabilities of all the configurations to be picked for the next piece, in order.
+    pub fn probabilities(&self) -> Vec<f64> {
+        (0..self.configurations.len()).filter_map(|i| self.probability(i)).collect()
+    }
+
     fn next_configuration<R: Rng>(&self, rng: &mut R) -> &PieceConfiguration {
-        let n = rng.next_u32();
+        let mut n = rng.gen_range(0..self.total_weight);
         for conf in self.configurations.iter() {
-            if n < conf.weight {return conf;}
+            if n < conf.weight as u64 {
+                return conf;
+            }
+            n -= conf.weight as u64;
         };
         unreachable!()
     }
@@ -31,61 +119,79 @@ impl CustomMode {
 
 impl<R: Rng> GameMode<R> for CustomMode {
     fn initialize(&mut self) {
-        // If no block types are specified, just use the standard block type.
-        if self.block_types.is_empty() {
-            self.block_types.push(Box::new(StandardType{}));
-        }
+        let max_n_blocks = self.configurations.iter().map(|conf| conf.n_blocks).max().unwrap_or(4);
+        self.shapes.gen_until(max_n_blocks);
+        // Non referenced block types are not discarded.
+    }
 
-        // If no configurations are specified, just use one for each block type.
-        if self.configurations.is_empty() {
-            self.configurations = (0..self.block_types.len()).map(|bt| {
-                PieceConfiguration {
-                    block_type: bt,
-                    n_blocks: 4,
-                    weight: 1,
-                }
-            }).collect();
-        }
+    fn randomizer(&self) -> Box<dyn Randomizer> {
+        self.randomizer.restarted()
+    }
 
-        // Sort configurations by weight desc as the bigger it is, the more likely to pick.
-        self.configurations
-            .sort_by(|conf1, conf2| {
-                conf2.weight.cmp(&conf1.weight)
-            });
-
-        self.configurations
-            .iter_mut()
-            .filter(|conf| {
-                // Discard invalid or unweighted configurations
-                conf.weight > 0 && conf.n_blocks > 0 && conf.block_type < self.block_types.len()
-            }).for_each(|conf| {
-                // Accumulate weights so it's easier to pick a random configuration.
-                self.total_weight += conf.weight;
-                conf.weight = self.total_weight;
-            });
+    fn next_piece(&self, randomizer: &mut dyn Randomizer, rng: &mut R) -> Piece<'_> {
+        let conf = self.next_configuration(rng);
+        let shapes = self.shapes.shapes(conf.n_blocks);
+        Piece::uniform(self.block_types[conf.block_type].as_ref(), shapes[randomizer.next(shapes.len(), rng)].clone())
+    }
 
-        // Non referenced block types are not discarded.
+    fn rotation_system(&self) -> &dyn RotationSystem {
+        self.rotation_system.as_ref()
+    }
 
-        let max_n_blocks = 
-            match self.configurations.iter().map(|conf| conf.n_blocks).max() {
-                Some(n_blocks) => n_blocks,
-                None => 4,
-            };
-        self.shapes.gen_until(max_n_blocks);
-        
-        // self.total_weight = self.configurations.iter().map(|conf| conf.weight).sum();
+    fn scoring_rule(&self) -> Box<dyn ScoringRule> {
+        self.scoring_rule.restarted()
     }
 
-    fn next_piece(&self, rng: &mut R) -> Piece {
-        let conf = self.next_configuration(rng);
-        Piece::uniform(self.block_types[conf.block_type].as_ref(), self.shapes.random(conf.n_blocks, rng))
+    fn level_curve(&self) -> Option<&LevelCurve> {
+        self.level_curve.as_ref()
     }
 }
 
-#[derive(Debug)]
+/// A kind of piece a `CustomMode` can pick: its `BlockType` (as an index in the block types of the mode), its number of
+/// blocks, and how likely it is to be picked relative to the other configurations.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct PieceConfiguration {
     block_type: usize,
     // block_state: State<u64>,
     n_blocks: usize,
     weight: u32,
-}
\ No newline at end of file
+}
+
+impl PieceConfiguration {
+    pub fn new(block_type: usize, n_blocks: usize, weight: u32) -> Self {
+        Self {
+            block_type,
+            n_blocks,
+            weight,
+        }
+    }
+
+    pub fn block_type(&self) -> usize { self.block_type }
+
+    pub fn n_blocks(&self) -> usize { self.n_blocks }
+
+    pub fn weight(&self) -> u32 { self.weight }
+}
+
+/// Why the configurations of a `CustomMode` are invalid. Each variant holds the index of the wrong configuration.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConfigurationError {
+    /// The configuration refers to a block type that doesn't exist.
+    UnknownBlockType(usize),
+    /// The configuration makes pieces without blocks.
+    NoBlocks(usize),
+    /// The configuration has no weight, so it could never be picked.
+    NoWeight(usize),
+}
+
+impl fmt::Display for ConfigurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownBlockType(i) => write!(f, "piece configuration {i} refers to an unknown block type"),
+            Self::NoBlocks(i) => write!(f, "piece configuration {i} has no blocks"),
+            Self::NoWeight(i) => write!(f, "piece configuration {i} has no weight"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigurationError {}
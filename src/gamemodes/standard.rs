@@ -1,11 +1,18 @@
 use rand::Rng;
 
 use crate::{gamemode::GameMode, piece::{shape::Shapes, Piece}, block::BlockType, blocktypes::standard::StandardType};
+use crate::{rotationsystem::RotationSystem, rotationsystems::SuperRotationSystem};
+use crate::{scoringrule::ScoringRule, scoringrules::GuidelineScoring, level::LevelCurve};
+use crate::{randomizer::Randomizer, randomizers::Bag};
 
 #[derive(Debug)]
 pub struct StandardMode {
     shapes: Shapes,
     standard_type: Box<dyn BlockType>,
+    rotation_system: Box<dyn RotationSystem>,
+    randomizer: Box<dyn Randomizer>,
+    scoring_rule: Box<dyn ScoringRule>,
+    level_curve: Option<LevelCurve>,
 }
 
 impl StandardMode {
@@ -13,8 +20,38 @@ impl StandardMode {
         Self {
             shapes: Shapes::new(),
             standard_type: Box::new(StandardType{}),
+            rotation_system: Box::new(SuperRotationSystem::new()),
+            randomizer: Box::new(Bag::new(1)),
+            scoring_rule: Box::new(GuidelineScoring::new()),
+            level_curve: Some(LevelCurve::guideline()),
         }
     }
+
+    /// Replaces the `RotationSystem` used (SRS by default).
+    pub fn set_rotation_system(&mut self, rotation_system: Box<dyn RotationSystem>) {
+        self.rotation_system = rotation_system;
+    }
+
+    /// Replaces the `Randomizer` used (7-bag by default). It picks among the 7 tetrominoes, in the order given by
+    /// `Shapes::shapes(4)`.
+    pub fn set_randomizer(&mut self, randomizer: Box<dyn Randomizer>) {
+        self.randomizer = randomizer;
+    }
+
+    /// Replaces the `ScoringRule` used (guideline by default).
+    pub fn set_scoring_rule(&mut self, scoring_rule: Box<dyn ScoringRule>) {
+        self.scoring_rule = scoring_rule;
+    }
+
+    /// Replaces the `LevelCurve` used (guideline by default), or removes it with `None` so that grids keep the physics
+    /// of their configuration.
+    pub fn set_level_curve(&mut self, level_curve: Option<LevelCurve>) {
+        self.level_curve = level_curve;
+    }
+}
+
+impl Default for StandardMode {
+    fn default() -> Self { Self::new() }
 }
 
 impl<R: Rng> GameMode<R> for StandardMode {
@@ -22,8 +59,25 @@ impl<R: Rng> GameMode<R> for StandardMode {
         self.shapes.gen_until(4);
     }
     
-    fn next_piece(&self, rng: &mut R) -> Piece {
-        Piece::uniform(self.standard_type.as_ref(), self.shapes.random(4, rng))
+    fn randomizer(&self) -> Box<dyn Randomizer> {
+        self.randomizer.restarted()
+    }
+
+    fn next_piece(&self, randomizer: &mut dyn Randomizer, rng: &mut R) -> Piece<'_> {
+        let shapes = self.shapes.shapes(4);
+        Piece::uniform(self.standard_type.as_ref(), shapes[randomizer.next(shapes.len(), rng)].clone())
+    }
+
+    fn rotation_system(&self) -> &dyn RotationSystem {
+        self.rotation_system.as_ref()
+    }
+
+    fn scoring_rule(&self) -> Box<dyn ScoringRule> {
+        self.scoring_rule.restarted()
+    }
+
+    fn level_curve(&self) -> Option<&LevelCurve> {
+        self.level_curve.as_ref()
     }
     
     // fn next_shape(&self, rng: &mut R) -> Shape {
@@ -0,0 +1,229 @@
+use std::collections::VecDeque;
+
+use rand::{seq::index::sample, Rng, RngCore};
+
+use crate::{block::{Block, BlockType}, grid::Grid};
+
+/// Lines of garbage received at once, waiting to enter a grid.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Garbage {
+    pub lines: u32,
+    /// Ticks left until the garbage can enter the grid.
+    pub timer: u32,
+}
+
+/// The garbage a grid received and that didn't enter it yet. Garbage waits a delay before it can enter, and meanwhile
+/// the attacks of the grid cancel it, oldest first.
+///
+/// ```
+/// use atris::garbage::GarbageQueue;
+///
+/// let mut queue = GarbageQueue::new(2);
+/// queue.push(3);
+/// queue.push(2);
+/// assert_eq!(queue.cancel(4), 0); // 4 lines cancel the first garbage and 1 line of the second one
+/// assert_eq!(queue.lines(), 1);
+/// queue.tick();
+/// assert!(queue.take_ready(8).is_empty());
+/// queue.tick();
+/// assert_eq!(queue.take_ready(8), vec!(1));
+/// queue.push(1);
+/// assert_eq!(queue.cancel(3), 2); // The rest of the attack is sent
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GarbageQueue {
+    delay: u32,
+    pending: VecDeque<Garbage>,
+}
+
+impl GarbageQueue {
+    /// Creates an empty queue where garbage waits `delay` ticks before it can enter the grid.
+    pub fn new(delay: u32) -> Self {
+        Self {
+            delay,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn delay(&self) -> u32 { self.delay }
+
+    /// Changes the delay of the garbage received from now on.
+    pub fn set_delay(&mut self, delay: u32) {
+        self.delay = delay;
+    }
+
+    /// Returns the garbage waiting, oldest first.
+    pub fn pending(&self) -> impl Iterator<Item = &Garbage> {
+        self.pending.iter()
+    }
+
+    /// Returns the total lines of garbage waiting.
+    pub fn lines(&self) -> u32 {
+        self.pending.iter().map(|garbage| garbage.lines).sum()
+    }
+
+    /// Adds lines of garbage received at once.
+    pub fn push(&mut self, lines: u32) {
+        if lines > 0 {
+            self.pending.push_back(Garbage { lines, timer: self.delay });
+        }
+    }
+
+    /// Advances the delay of the garbage waiting one tick.
+    pub fn tick(&mut self) {
+        for garbage in self.pending.iter_mut() {
+            garbage.timer = garbage.timer.saturating_sub(1);
+        }
+    }
+
+    /// Cancels the garbage waiting with an attack, oldest first, and returns the lines of the attack left to send.
+    pub fn cancel(&mut self, mut attack: u32) -> u32 {
+        while attack > 0 {
+            let Some(garbage) = self.pending.front_mut() else {
+                break;
+            };
+            let cancelled = garbage.lines.min(attack);
+            garbage.lines -= cancelled;
+            attack -= cancelled;
+            if garbage.lines == 0 {
+                self.pending.pop_front();
+            }
+        }
+        attack
+    }
+
+    /// Takes the garbage whose delay is over, oldest first, up to `max_lines` lines. The lines of each garbage are
+    /// returned separately.
+    pub fn take_ready(&mut self, max_lines: u32) -> Vec<u32> {
+        let mut ready = Vec::new();
+        let mut left = max_lines;
+        while left > 0 {
+            let Some(garbage) = self.pending.front_mut().filter(|garbage| garbage.timer == 0) else {
+                break;
+            };
+            let lines = garbage.lines.min(left);
+            garbage.lines -= lines;
+            left -= lines;
+            ready.push(lines);
+            if garbage.lines == 0 {
+                self.pending.pop_front();
+            }
+        }
+        ready
+    }
+}
+
+/// Where the holes of garbage rows are.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GarbageStyle {
+    /// All the rows of the same garbage have their hole in the same column.
+    #[default]
+    Clean,
+    /// Each row has the given chance, in percent (100 above 100), of having its hole in another column than the row
+    /// generated before it.
+    Messy(u32),
+    /// Each row has its hole in another column than the row generated before it.
+    Cheese,
+    /// Each row has the given number of holes, in random columns.
+    MultiHole(usize),
+}
+
+/// Generates rows of garbage following a `GarbageStyle`, remembering the holes of the last row so the next garbage can
+/// follow them.
+///
+/// ```
+/// use atris::algebra::Vector;
+/// use atris::blocktypes::rock::RockType;
+/// use atris::garbage::{GarbageGenerator, GarbageStyle};
+/// use atris::grid::Grid;
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let bt = RockType{};
+/// let mut rng = StdRng::seed_from_u64(0);
+/// let mut grid = Grid::new(Vector(10, 4));
+/// let mut generator = GarbageGenerator::new(GarbageStyle::Cheese);
+/// assert!(!generator.insert(&mut grid, 3, &bt, &mut rng));
+/// let holes: Vec<usize> = (0..3).map(|i| (0..10).find(|&j| grid[Vector(j, i)].is_none()).unwrap()).collect();
+/// assert!(holes[0] != holes[1] && holes[1] != holes[2]);
+/// assert!(grid.empty_row(3));
+/// assert!(generator.insert(&mut grid, 2, &bt, &mut rng)); // Tops out
+///
+/// let mut generator = GarbageGenerator::new(GarbageStyle::MultiHole(2));
+/// let rows = generator.rows(2, 10, &bt, &mut rng);
+/// assert!(rows.iter().all(|row| row.iter().filter(|cell| cell.is_none()).count() == 2));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GarbageGenerator {
+    style: GarbageStyle,
+    /// Holes of the last row generated.
+    holes: Vec<usize>,
+}
+
+impl GarbageGenerator {
+    pub fn new(style: GarbageStyle) -> Self {
+        Self {
+            style,
+            holes: Vec::new(),
+        }
+    }
+
+    pub fn style(&self) -> GarbageStyle { self.style }
+
+    /// Changes the style of the garbage generated from now on.
+    pub fn set_style(&mut self, style: GarbageStyle) {
+        self.style = style;
+    }
+
+    /// Returns the columns of the holes of the next row, given whether it's the first row of a garbage.
+    fn next_holes(&mut self, n_cols: usize, first: bool, rng: &mut dyn RngCore) -> Vec<usize> {
+        if n_cols == 0 {
+            return Vec::new();
+        }
+        let previous = match self.holes.as_slice() {
+            &[hole] if hole < n_cols => Some(hole),
+            _ => None,
+        };
+        let other_column = |rng: &mut dyn RngCore| match previous {
+            Some(hole) if n_cols > 1 => (hole + rng.gen_range(1..n_cols)) % n_cols,
+            _ => rng.gen_range(0..n_cols),
+        };
+        self.holes = match self.style {
+            GarbageStyle::Clean => match previous {
+                Some(hole) if !first => vec!(hole),
+                _ => vec!(rng.gen_range(0..n_cols)),
+            },
+            GarbageStyle::Messy(chance) => match previous {
+                Some(hole) if !rng.gen_ratio(chance.min(100), 100) => vec!(hole),
+                _ => vec!(other_column(rng)),
+            },
+            GarbageStyle::Cheese => vec!(other_column(rng)),
+            GarbageStyle::MultiHole(n) => sample(rng, n_cols, n.min(n_cols)).into_vec(),
+        };
+        self.holes.clone()
+    }
+
+    /// Generates the rows of a garbage of `lines` lines, bottom to top, made of blocks of `block_type` for a grid of
+    /// `n_cols` columns.
+    pub fn rows<'bt>(&mut self, lines: u32, n_cols: usize, block_type: &'bt dyn BlockType, rng: &mut dyn RngCore)
+            -> Vec<Vec<Option<Block<'bt>>>> {
+        let mut rows: Vec<Vec<Option<Block<'bt>>>> = (0..lines).map(|i| {
+            let holes = self.next_holes(n_cols, i == 0, rng);
+            (0..n_cols).map(|j| (!holes.contains(&j)).then(|| Block::new(block_type))).collect()
+        }).collect();
+        // The rows were generated top to bottom, as each one is put below the previous ones
+        rows.reverse();
+        rows
+    }
+
+    /// Inserts a garbage of `lines` lines at the bottom of `grid`, pushing its blocks up, and returns whether some of
+    /// them were pushed past the top.
+    pub fn insert<'bt>(&mut self, grid: &mut Grid<'bt>, lines: u32, block_type: &'bt dyn BlockType,
+            rng: &mut dyn RngCore) -> bool {
+        let rows = self.rows(lines, grid.n_cols(), block_type, rng);
+        grid.raise(rows)
+    }
+}
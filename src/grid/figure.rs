@@ -1,26 +1,168 @@
 use crate::algebra::IVector;
 use crate::piece::Piece;
+use crate::piece::shape::Shape;
+use crate::rotationsystem::RotationSystem;
+#[cfg(feature = "serde")]
+use crate::{piece::PieceData, blocktypes::BlockTypes, snapshot::{deserialize_resolved, DeserializeWith, Unresolved}};
 
-#[derive(Debug)]
+/// A `Piece` placed on a `Grid`, with a position and a rotation state. The rotation state is the number of
+/// counter-clockwise quarter turns applied to the `Piece` since it spawned, always between 0 and 3.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Figure<'bt> {
     piece: Piece<'bt>,
     pub position: IVector,
+    rotation: i32,
+    orientations: Vec<Shape>,
 }
 
 impl<'bt> Figure<'bt> {
-    // /// Constructs a new Figure from a (borrowed) `BlockType` and a `Shape`. All the blocks will have the same type.
-    // pub fn uniform(t: &'bt dyn BlockType, shape: Shape) -> Self {
-    //     let mut blocks = Vec::new();
-    //     blocks.resize(shape.positions().len(), Block::new(t));
-    //     Self {
-    //         piece: Piece,
-    //         position: Vector::default(),
-    //     }
-    // }
+    /// Constructs a new `Figure` from a `Piece` at some position, with its initial rotation state (0). The `Figure`
+    /// rotates around the origin of the `Piece`'s `Shape`.
+    pub fn new(piece: Piece<'bt>, position: IVector) -> Self {
+        let orientations = (0..4).map(|angle| piece.shape().rotated(angle)).collect();
+        Self {
+            piece,
+            position,
+            rotation: 0,
+            orientations,
+        }
+    }
 
-    pub fn piece(&self) -> &Piece { &self.piece }
+    /// Constructs a new `Figure` from a `Piece` at some position, with its initial rotation state (0). Its orientations
+    /// are given by `rotation_system`.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::grid::Figure;
+    /// use atris::piece::{shape::{Shape, Tetromino}, Piece};
+    /// use atris::blocktypes::standard::StandardType;
+    /// use atris::rotationsystems::SuperRotationSystem;
+    ///
+    /// let bt = StandardType{};
+    /// let t = Tetromino::T.shape().rotated(2);
+    /// let fig = Figure::with_rotation_system(Piece::uniform(&bt, t), Vector(4, 18), &SuperRotationSystem::new());
+    /// assert_eq!(fig.shape().positions(), Tetromino::T.shape().positions());
+    /// ```
+    pub fn with_rotation_system(piece: Piece<'bt>, position: IVector, rotation_system: &dyn RotationSystem) -> Self {
+        let orientations = rotation_system.orientations(piece.shape());
+        Self {
+            piece,
+            position,
+            rotation: 0,
+            orientations,
+        }
+    }
 
-    // pub fn blocks(&self) -> &Vec<Block<'bt>> { &self.blocks }
+    pub fn piece(&self) -> &Piece<'bt> { &self.piece }
 
-    // pub fn shape(&self) -> &Shape { &self.shape }
+    /// Returns the current rotation state, between 0 and 3.
+    pub fn rotation(&self) -> i32 { self.rotation }
+
+    /// Sets the rotation state. Any `angle` is accepted, and it's normalized between 0 and 3.
+    pub fn set_rotation(&mut self, angle: i32) {
+        self.rotation = angle.rem_euclid(4);
+    }
+
+    /// Returns the `Shape` of the `Figure` rotated `angle` more quarter turns than its current rotation state,
+    /// relative to its position.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::grid::Figure;
+    /// use atris::piece::{shape::Shape, Piece};
+    /// use atris::blocktypes::standard::StandardType;
+    ///
+    /// let bt = StandardType{};
+    /// let shape = Shape::from_iter([Vector(-1, 0), Vector(0, 0), Vector(1, 0)]);
+    /// let mut fig = Figure::new(Piece::uniform(&bt, shape.clone()), Vector(4, 4));
+    /// assert_eq!(fig.rotated_shape(0).positions(), shape.positions());
+    /// assert_eq!(fig.rotated_shape(1).positions(), &vec!(Vector(0, -1), Vector(0, 0), Vector(0, 1)));
+    /// fig.set_rotation(1);
+    /// assert_eq!(fig.rotated_shape(-1).positions(), shape.positions());
+    /// ```
+    pub fn rotated_shape(&self, angle: i32) -> Shape {
+        self.orientations[(self.rotation + angle).rem_euclid(4) as usize].clone()
+    }
+
+    /// Returns the `Shape` of the `Figure` in its current rotation state, relative to its position.
+    pub fn shape(&self) -> Shape { self.rotated_shape(0) }
+
+    /// Returns the absolute positions (in the `Grid`) of the `Figure`'s blocks.
+    pub fn cells(&self) -> Shape { self.shape() + self.position }
+
+    /// Returns the absolute positions (in the `Grid`) of the `Figure`'s blocks, in the order of the blocks of its
+    /// `Piece`. Each block follows the rotations of the `Figure`, unlike the points of a `Shape`, which are sorted.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::grid::Figure;
+    /// use atris::piece::{shape::Shape, Piece};
+    /// use atris::blocktypes::standard::StandardType;
+    ///
+    /// let bt = StandardType{};
+    /// let shape = Shape::from_iter([Vector(-1, 0), Vector(0, 0), Vector(1, 0)]);
+    /// let mut fig = Figure::new(Piece::uniform(&bt, shape), Vector(4, 4));
+    /// fig.set_rotation(1);
+    /// assert_eq!(fig.block_cells(), vec!(Vector(4, 3), Vector(4, 4), Vector(4, 5)));
+    /// fig.set_rotation(3);
+    /// assert_eq!(fig.block_cells(), vec!(Vector(4, 5), Vector(4, 4), Vector(4, 3)));
+    /// ```
+    pub fn block_cells(&self) -> Vec<IVector> {
+        let shape = self.piece.shape();
+        // The orientations may be translated, or start from another rotation of the `Piece`'s `Shape`
+        let spawn_angle = (0..4).find(|&angle| Self::offset(shape, angle, &self.orientations[0]).is_some())
+            .unwrap_or(0);
+        let current = self.shape();
+        (0..4).map(|turns| spawn_angle + self.rotation + turns)
+            .find_map(|angle| Some((angle, Self::offset(shape, angle, &current)?)))
+            .map_or_else(|| self.cells().positions().clone(), |(angle, offset)| {
+                shape.iter().map(|p| p.rotated(angle) + offset + self.position).collect()
+            })
+    }
+
+    /// Returns the offset that moves `shape` rotated `angle` quarter turns onto `target`, if they match.
+    fn offset(shape: &Shape, angle: i32, target: &Shape) -> Option<IVector> {
+        let rotated = shape.rotated(angle);
+        if rotated.is_empty() || rotated.len() != target.len() {
+            return None;
+        }
+        let offset = target.bounds().0 - rotated.bounds().0;
+        Some(offset).filter(|&offset| (rotated + offset).positions() == target.positions())
+    }
+}
+
+/// Serialized form of a `Figure`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+pub(crate) struct FigureData {
+    piece: PieceData,
+    position: IVector,
+    rotation: i32,
+    orientations: Vec<Shape>,
+}
+
+#[cfg(feature = "serde")]
+impl<'bt> Unresolved<'bt> for FigureData {
+    type Resolved = Figure<'bt>;
+
+    fn resolve(self, block_types: &'bt BlockTypes) -> Result<Figure<'bt>, String> {
+        if self.orientations.len() != 4 || !(0..4).contains(&self.rotation) {
+            return Err("figure without 4 orientations, or with an invalid rotation state".to_string());
+        }
+        Ok(Figure {
+            piece: self.piece.resolve(block_types)?,
+            position: self.position,
+            rotation: self.rotation,
+            orientations: self.orientations,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'bt> DeserializeWith<'bt> for Figure<'bt> {
+    fn deserialize_with<'de, D: serde::Deserializer<'de>>(deserializer: D, block_types: &'bt BlockTypes)
+            -> Result<Self, D::Error> {
+        deserialize_resolved::<FigureData, D>(deserializer, block_types)
+    }
 }
@@ -1,20 +1,43 @@
 // use std::iter::Zip;
 use std::ops::{Index, IndexMut};
 
-use crate::block::{Block, PositionedBlock};
-use crate::algebra::{Vector, UVector, IVector, FVector};
-use super::Figure;
+use crate::block::Block;
+use crate::algebra::{Vector, UVector, IVector};
+use crate::piece::shape::Shape;
+use crate::rotationsystem::RotationSystem;
+use super::{Figure, Movement, Kick};
+#[cfg(feature = "serde")]
+use crate::{block::BlockData, blocktypes::BlockTypes, snapshot::{deserialize_resolved, DeserializeWith, Unresolved}};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Grid<'bt> {
     bounds: UVector,
+    /// Rows at the bottom that are visible. The ones above them are a hidden buffer where figures spawn.
+    visible_rows: usize,
     rows: Vec<Vec<Option<Block<'bt>>>>,
     // blocks: Vec<PositionedBlock>,
 }
 
 impl<'bt> Grid<'bt> {
+    /// Creates an empty `Grid` with `bounds` (columns, rows), all of them visible.
     pub fn new(bounds: UVector) -> Self {
-        let mut g = Self { bounds: bounds, rows: Vec::new() };
+        Self::with_hidden_rows(bounds, 0)
+    }
+
+    /// Creates an empty `Grid` with `visible_bounds` (columns, rows), and `hidden_rows` more rows above them.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::grid::Grid;
+    ///
+    /// let grid = Grid::with_hidden_rows(Vector(10, 20), 4);
+    /// assert_eq!(grid.bounds(), Vector(10, 24));
+    /// assert_eq!((grid.visible_rows(), grid.hidden_rows()), (20, 4));
+    /// ```
+    pub fn with_hidden_rows(visible_bounds: UVector, hidden_rows: usize) -> Self {
+        let bounds = Vector(visible_bounds.0, visible_bounds.1 + hidden_rows);
+        let mut g = Self { bounds, visible_rows: visible_bounds.1, rows: Vec::new() };
         let mut row = Vec::new();
         row.resize(bounds.0, None);
         g.rows.resize(bounds.1, row);
@@ -26,6 +49,36 @@ impl<'bt> Grid<'bt> {
     pub fn n_cols(&self) -> usize { self.bounds.0 }
     pub fn n_rows(&self) -> usize { self.bounds.1 }
 
+    /// Returns the number of visible rows, at the bottom of the `Grid`.
+    pub fn visible_rows(&self) -> usize { self.visible_rows }
+
+    /// Returns the number of hidden rows, above the visible ones.
+    pub fn hidden_rows(&self) -> usize { self.n_rows() - self.visible_rows }
+
+    /// Returns the position where a `Figure` with `shape` (relative to its position) spawns by default: horizontally
+    /// centered, leaning left, and with its lowest row right above the visible rows, or with its highest row at the
+    /// top if there is no room there. Works for any `Shape`.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::grid::Grid;
+    /// use atris::piece::shape::Tetromino;
+    ///
+    /// let grid = Grid::with_hidden_rows(Vector(10, 20), 2);
+    /// let t = Tetromino::T.shape(); // From (-1, 0) to (1, 1)
+    /// assert_eq!(grid.spawn_position(&t), Vector(4, 20)); // Columns 3 to 5
+    /// assert_eq!(grid.spawn_position(&Tetromino::I.shape().rotated(1)), Vector(4, 19)); // Taller than the hidden rows
+    /// let grid = Grid::new(Vector(10, 20));
+    /// assert_eq!(grid.spawn_position(&t), Vector(4, 18));
+    /// ```
+    pub fn spawn_position(&self, shape: &Shape) -> IVector {
+        let (min, max) = shape.bounds();
+        let width = max.0 - min.0 + 1;
+        let x = (self.n_cols() as i32 - width).div_euclid(2) - min.0;
+        let y = (self.visible_rows() as i32 - min.1).min(self.n_rows() as i32 - 1 - max.1);
+        Vector(x, y)
+    }
+
     pub fn put(&mut self, pos: UVector, b: Block<'bt>) -> Option<Block<'bt>> {
         let old = self.rows[pos.1][pos.0].replace(b);
         // match self.blocks.binary_search(&pb) {
@@ -44,15 +97,160 @@ impl<'bt> Grid<'bt> {
         Some(b)
     }
 
-    pub fn try_move(&self, fig: &Figure<'bt>, x_offset: i32) {todo!()}
+    /// Moves `fig` by `offset` if it fits there, and returns the resulting `Movement`. Moves are never kicked.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::grid::{Figure, Grid};
+    /// use atris::piece::{shape::Shape, Piece};
+    /// use atris::blocktypes::standard::StandardType;
+    ///
+    /// let bt = StandardType{};
+    /// let grid = Grid::new(Vector(5, 5));
+    /// let shape = Shape::from_iter([Vector(-1, 0), Vector(0, 0), Vector(1, 0)]);
+    /// let mut fig = Figure::new(Piece::uniform(&bt, shape), Vector(2, 4));
+    /// let m = grid.try_move(&mut fig, Vector(1, 0));
+    /// assert!(m.success);
+    /// assert_eq!(fig.position, Vector(3, 4));
+    /// let m = grid.try_move(&mut fig, Vector(1, 0));
+    /// assert!(!m.success);
+    /// assert_eq!(m.position, Vector(3, 4));
+    /// assert_eq!(fig.position, Vector(3, 4));
+    /// ```
+    pub fn try_move(&self, fig: &mut Figure<'bt>, offset: IVector) -> Movement {
+        if !self.fits_in(fig, offset, 0) {
+            return Movement::failed(fig.position, fig.rotation());
+        }
+        fig.position = fig.position + offset;
+        Movement { success: true, position: fig.position, rotation: fig.rotation(), kick: None }
+    }
 
-    pub fn try_rotate(&self, fig: &Figure<'bt>, angle: i32) {todo!()}
+    /// Rotates `fig` `angle` quarter turns counter-clockwise if it fits, testing the kicks given by `rotation_system`
+    /// in order, and returns the resulting `Movement`.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::grid::{Figure, Grid, Kick};
+    /// use atris::piece::{shape::Tetromino, Piece};
+    /// use atris::blocktypes::standard::StandardType;
+    /// use atris::rotationsystems::SuperRotationSystem;
+    ///
+    /// let bt = StandardType{};
+    /// let srs = SuperRotationSystem::new();
+    /// let grid = Grid::new(Vector(10, 20));
+    /// let mut fig = Figure::with_rotation_system(Piece::uniform(&bt, Tetromino::T.shape()), Vector(4, 10), &srs);
+    /// let m = grid.try_rotate(&mut fig, -1, &srs);
+    /// assert!(m.success);
+    /// assert_eq!(m.kick, None);
+    /// assert_eq!((fig.position, fig.rotation()), (Vector(4, 10), 3));
+    /// // Against the left wall, R->0 kicks the T one cell to the right
+    /// fig.position = Vector(0, 10);
+    /// let m = grid.try_rotate(&mut fig, 1, &srs);
+    /// assert_eq!(m.kick, Some(Kick { index: 1, offset: Vector(1, 0), last: false }));
+    /// assert_eq!((fig.position, fig.rotation()), (Vector(1, 10), 0));
+    /// ```
+    pub fn try_rotate(&self, fig: &mut Figure<'bt>, angle: i32, rotation_system: &dyn RotationSystem) -> Movement {
+        let shape = fig.piece().shape();
+        let from = fig.rotation();
+        let to = (from + angle).rem_euclid(4);
+        let kicks = rotation_system.kicks(shape, from, to);
+        for (index, &offset) in kicks.iter().enumerate() {
+            if self.fits_in(fig, offset, angle) {
+                fig.position = fig.position + offset;
+                fig.set_rotation(to);
+                let kick = if index > 0 {Some(Kick { index, offset, last: index + 1 == kicks.len() })} else {None};
+                return Movement { success: true, position: fig.position, rotation: to, kick };
+            }
+            if index == 0 {
+                let obstructed: Vec<IVector> = fig.rotated_shape(angle).iter()
+                    .filter(|&&p| !self.pos_available(fig.position + p + offset))
+                    .copied().collect();
+                if !rotation_system.kick_allowed(shape, from, to, &obstructed) {
+                    break;
+                }
+            }
+        }
+        Movement::failed(fig.position, from)
+    }
+
+    /// Returns how many rows `fig` can fall until it lands on a block or on the bottom of the `Grid`.
+    pub fn drop_distance(&self, fig: &Figure<'bt>) -> i32 {
+        let mut distance = 0;
+        while self.fits_in(fig, Vector(0, -distance - 1), 0) {
+            distance += 1;
+        }
+        distance
+    }
+
+    /// Returns the position where `fig` would land if it was hard dropped, this is, the position of its ghost. As it's
+    /// computed from the current position and rotation of `fig`, it must be asked again after `fig` moves or rotates.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::grid::{Figure, Grid};
+    /// use atris::piece::{shape::{Shape, Tetromino}, Piece};
+    /// use atris::blocktypes::standard::StandardType;
+    /// use atris::rotationsystems::SuperRotationSystem;
+    ///
+    /// let bt = StandardType{};
+    /// let mut grid = Grid::new(Vector(10, 20));
+    /// grid.place(&Figure::new(Piece::uniform(&bt, Tetromino::O.shape()), Vector(0, 0)));
+    /// let srs = SuperRotationSystem::new();
+    /// let mut fig = Figure::with_rotation_system(Piece::uniform(&bt, Tetromino::I.shape()), Vector(4, 18), &srs);
+    /// assert_eq!(grid.ghost_position(&fig), Vector(4, 0));
+    /// grid.try_move(&mut fig, Vector(-3, 0));
+    /// assert_eq!(grid.ghost_position(&fig), Vector(1, 2));
+    /// assert_eq!(grid.ghost_cells(&fig), Shape::from_iter([Vector(0, 2), Vector(1, 2), Vector(2, 2), Vector(3, 2)]));
+    /// grid.try_rotate(&mut fig, 1, &srs);
+    /// assert_eq!(grid.ghost_cells(&fig), Shape::from_iter([Vector(1, 2), Vector(1, 3), Vector(1, 4), Vector(1, 5)]));
+    /// ```
+    pub fn ghost_position(&self, fig: &Figure<'bt>) -> IVector {
+        fig.position + Vector(0, -self.drop_distance(fig))
+    }
+
+    /// Returns the absolute positions (in the `Grid`) of the blocks of the ghost of `fig`, this is, where they would be
+    /// if it was hard dropped.
+    pub fn ghost_cells(&self, fig: &Figure<'bt>) -> Shape {
+        fig.shape() + self.ghost_position(fig)
+    }
+
+    /// Copies the blocks of `fig` into the `Grid`, at its current position, and returns the positions where they were
+    /// put. Blocks out of the `Grid` bounds are discarded.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::grid::{Figure, Grid};
+    /// use atris::piece::{shape::Tetromino, Piece};
+    /// use atris::blocktypes::standard::StandardType;
+    ///
+    /// let bt = StandardType{};
+    /// let mut grid = Grid::new(Vector(10, 20));
+    /// let mut fig = Figure::new(Piece::uniform(&bt, Tetromino::O.shape()), Vector(4, 18));
+    /// assert_eq!(grid.drop_distance(&fig), 18);
+    /// fig.position = Vector(4, 0);
+    /// assert_eq!(grid.place(&fig), vec!(Vector(4, 0), Vector(4, 1), Vector(5, 0), Vector(5, 1)));
+    /// assert!(grid[Vector(5, 1)].is_some());
+    /// fig.position = Vector(4, 18);
+    /// assert_eq!(grid.drop_distance(&fig), 16);
+    /// ```
+    pub fn place(&mut self, fig: &Figure<'bt>) -> Vec<UVector> {
+        let mut placed = Vec::new();
+        for (block, p) in fig.piece().blocks().iter().zip(fig.block_cells()) {
+            if self.pos_in_bounds(p) {
+                let pos = UVector::try_from(p).unwrap();
+                self.put(pos, block.clone());
+                placed.push(pos);
+            }
+        }
+        placed
+    }
 
     // fn pos_as_usize(&self, p: UVector) -> usize { self.bounds.0*p.1 + p.0 }
 
+    /// Checks whether `fig` would fit in the `Grid` if it was moved by `offset` and rotated `angle` more quarter turns.
     pub fn fits_in(&self, fig: &Figure<'bt>, offset: IVector, angle: i32) -> bool {
         // for (b, &p) in fig.iter() { // To exclude ghost blocks
-        for &p in fig.piece().shape().rotated(angle).iter() {
+        for &p in fig.rotated_shape(angle).iter() {
             let pos = fig.position + p + offset;
             if !self.pos_available(pos) {
                 return false;
@@ -61,61 +259,18 @@ impl<'bt> Grid<'bt> {
         true
     }
 
-    pub fn figure_pos_correction(&self, fig: Figure<'bt>, x_offset: i32, angle: i32) -> Option<IVector> {
-        let offset = Vector(x_offset, 0);
-        let rot_shape = fig.piece().shape().rotated(angle);
-        let fig_center = <FVector>::from(fig.position) + rot_shape.f64_center();
-        let mut correction = Vector(0i32, 0i32);
-        for &p in rot_shape.iter() {
-            let pos = fig.position + p + offset;
-            let corr: IVector = self.pos_correction(pos, fig_center)?;
-            // Check that one correction does not contradict others:
-            if corr.0 != 0 && correction.0.signum() == -corr.0.signum() { //) ||
-                // (corr.1 != 0 && correction.1.signum() == -corr.1.signum()) {
-                return None;
-            }
-            if corr.0.abs() > correction.0.abs() {
-                correction.0 = corr.0;
-            }
-        };
-        for &p in rot_shape.iter() {
-            if !self.pos_available(p + correction) {
-                return None;
-            }
-        };
-        Some(correction)
-    }
-
     pub fn pos_available(&self, pos: IVector) -> bool {
         self.pos_in_bounds(pos) && !self.pos_overlaps(pos)
     }
 
     pub fn pos_overlaps(&self, pos: IVector) -> bool {
         // Devolver false si el bloque es fantasma
-        self[UVector::try_from(pos).unwrap()].is_some()
+        self.pos_in_bounds(pos) && self[UVector::try_from(pos).unwrap()].is_some()
     }
 
     pub fn pos_in_bounds(&self, pos: IVector) -> bool {
         (0..self.n_cols() as i32).contains(&pos.0) && (0..self.n_rows() as i32).contains(&pos.1)
     }
-    
-    pub fn pos_correction(&self, pos: IVector, figure_center: FVector) -> Option<IVector> {
-        let d_sign = (figure_center.0 - pos.0 as f64).signum();
-        let mut correction = Vector(0, 0);
-        if pos.0 < 0 {correction.0 = -pos.0}
-        else if pos.0 >= self.n_cols() as i32 {correction.0 = pos.0 - self.n_cols() as i32 - 1};
-        // if pos.1 < 0 {correction.1 = -pos.1}
-        // else if pos.1 >= self.n_rows() as i32 {correction.1 = pos.1 - self.n_rows() as i32 - 1};
-        if d_sign != 0.0 {
-            while self.pos_overlaps(pos + correction) {
-                correction.0 += if d_sign > 0.0 {1} else {-1};
-                if !self.pos_in_bounds(pos + correction) {
-                    return None;
-                }
-            };
-        }
-        Some(correction)
-    }
 
     pub fn full_rows(&self) -> Vec<usize> {
         let mut full_rows = Vec::new();
@@ -127,11 +282,68 @@ impl<'bt> Grid<'bt> {
         full_rows
     }
 
+    /// Deletes the full rows and makes the rows above them fall, and returns the indices of the deleted rows in
+    /// ascending order.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::block::Block;
+    /// use atris::grid::Grid;
+    /// use atris::blocktypes::standard::StandardType;
+    ///
+    /// let bt = StandardType{};
+    /// let mut grid = Grid::new(Vector(3, 5));
+    /// for x in 0..3 {
+    ///     grid.put(Vector(x, 0), Block::new(&bt));
+    ///     grid.put(Vector(x, 2), Block::new(&bt));
+    /// }
+    /// grid.put(Vector(1, 1), Block::new(&bt));
+    /// grid.put(Vector(2, 3), Block::new(&bt));
+    /// assert_eq!(grid.clear_full_rows(), vec!(0, 2));
+    /// assert!(grid[Vector(1, 0)].is_some());
+    /// assert!(grid[Vector(2, 1)].is_some());
+    /// assert_eq!(grid.last_non_empty_row(), Some(1));
+    /// assert!(grid.full_rows().is_empty());
+    /// ```
+    pub fn clear_full_rows(&mut self) -> Vec<usize> {
+        let full_rows = self.full_rows();
+        self.delete_rows(&full_rows)
+    }
+
+    /// Deletes the empty rows below the last non-empty row and makes the rows above them fall, and returns the indices
+    /// of the deleted rows.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::block::Block;
+    /// use atris::grid::Grid;
+    /// use atris::blocktypes::standard::StandardType;
+    ///
+    /// let bt = StandardType{};
+    /// let mut grid = Grid::new(Vector(3, 5));
+    /// grid.put(Vector(0, 1), Block::new(&bt));
+    /// grid.put(Vector(1, 3), Block::new(&bt));
+    /// assert_eq!(grid.empty_rows(), vec!(2, 0));
+    /// assert_eq!(grid.delete_empty_rows(), vec!(2, 0));
+    /// assert!(grid[Vector(0, 0)].is_some());
+    /// assert!(grid[Vector(1, 1)].is_some());
+    /// assert_eq!(grid.last_non_empty_row(), Some(1));
+    /// ```
     pub fn delete_empty_rows(&mut self) -> Vec<usize> {
         let empty_rows = self.empty_rows();
+        self.delete_rows(&empty_rows);
+        empty_rows
+    }
+
+    /// Deletes some rows and makes the rows above them fall, adding new empty rows at the top. Returns the indices of
+    /// the deleted rows in ascending order, ignoring the ones out of bounds.
+    pub fn delete_rows(&mut self, rows: &[usize]) -> Vec<usize> {
+        let mut deleted_rows: Vec<usize> = rows.iter().copied().filter(|&i| i < self.n_rows()).collect();
+        deleted_rows.sort_unstable();
+        deleted_rows.dedup();
         let mut new_rows = Vec::new();
         for i in 0..self.n_rows() {
-            if !empty_rows.contains(&i) {
+            if deleted_rows.binary_search(&i).is_err() {
                 new_rows.push(self.rows[i].clone());
             }
         }
@@ -141,7 +353,7 @@ impl<'bt> Grid<'bt> {
             new_rows.push(row);
         }
         self.rows = new_rows;
-        empty_rows
+        deleted_rows
 
         // for &i in empty_rows.iter() {
         //     self.rows.remove(i);
@@ -153,10 +365,42 @@ impl<'bt> Grid<'bt> {
         // }
     }
 
+    /// Inserts `new_rows` at the bottom of the `Grid` (the first one becomes row 0), pushing the other rows up. Rows are
+    /// resized to the width of the `Grid`, and the ones pushed past the top are discarded. Returns whether any block
+    /// was pushed out this way.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::block::Block;
+    /// use atris::grid::Grid;
+    /// use atris::blocktypes::standard::StandardType;
+    ///
+    /// let bt = StandardType{};
+    /// let mut grid = Grid::new(Vector(3, 3));
+    /// grid.put(Vector(0, 1), Block::new(&bt));
+    /// let row = vec!(Some(Block::new(&bt)), None, Some(Block::new(&bt)));
+    /// assert!(!grid.raise(vec!(row.clone())));
+    /// assert!(grid[Vector(0, 2)].is_some());
+    /// assert!(grid[Vector(2, 0)].is_some() && grid[Vector(1, 0)].is_none());
+    /// assert!(grid.raise(vec!(row)));
+    /// ```
+    pub fn raise(&mut self, new_rows: Vec<Vec<Option<Block<'bt>>>>) -> bool {
+        let n_rows = self.n_rows();
+        let mut rows: Vec<Vec<Option<Block<'bt>>>> = new_rows.into_iter().map(|mut row| {
+            row.resize(self.n_cols(), None);
+            row
+        }).collect();
+        rows.append(&mut self.rows);
+        let pushed_out = rows.iter().skip(n_rows).any(|row| row.iter().any(|cell| cell.is_some()));
+        rows.truncate(n_rows);
+        self.rows = rows;
+        pushed_out
+    }
+
     pub fn empty_rows(&self) -> Vec<usize> {
         let mut empty_rows = Vec::new();
         let mut all_empty = true;
-        for i in self.n_rows()-1..=0 {
+        for i in (0..self.n_rows()).rev() {
             if all_empty {
                 if !self.empty_row(i) {
                     all_empty = false;
@@ -171,12 +415,7 @@ impl<'bt> Grid<'bt> {
     }
 
     pub fn last_non_empty_row(&self) -> Option<usize> {
-        for i in self.n_rows()-1..=0 {
-            if !self.empty_row(i) {
-                return Some(i);
-            }
-        };
-        None
+        (0..self.n_rows()).rev().find(|&i| !self.empty_row(i))
     }
 
     pub fn full_row(&self, i: usize) -> bool {
@@ -270,4 +509,34 @@ impl<'bt> IndexMut<UVector> for Grid<'bt> {
 //             None
 //         }
 //     }
-// }
\ No newline at end of file
+// }
+
+/// Serialized form of a `Grid`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+pub(crate) struct GridData {
+    bounds: UVector,
+    visible_rows: usize,
+    rows: Vec<Vec<Option<BlockData>>>,
+}
+
+#[cfg(feature = "serde")]
+impl<'bt> Unresolved<'bt> for GridData {
+    type Resolved = Grid<'bt>;
+
+    fn resolve(self, block_types: &'bt BlockTypes) -> Result<Grid<'bt>, String> {
+        if self.rows.len() != self.bounds.1 || self.rows.iter().any(|row| row.len() != self.bounds.0)
+                || self.visible_rows > self.bounds.1 {
+            return Err("grid with rows that don't match its bounds".to_string());
+        }
+        Ok(Grid { bounds: self.bounds, visible_rows: self.visible_rows, rows: self.rows.resolve(block_types)? })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'bt> DeserializeWith<'bt> for Grid<'bt> {
+    fn deserialize_with<'de, D: serde::Deserializer<'de>>(deserializer: D, block_types: &'bt BlockTypes)
+            -> Result<Self, D::Error> {
+        deserialize_resolved::<GridData, D>(deserializer, block_types)
+    }
+}
@@ -1,5 +1,13 @@
 mod grid;
 mod figure;
+mod movement;
+mod spawn;
+mod spin;
 
 pub use grid::Grid;
-pub use figure::Figure;
\ No newline at end of file
+pub use figure::Figure;
+pub use movement::{Movement, Kick};
+pub use spawn::{Spawn, SpawnRules};
+pub use spin::{Spin, SpinRule};
+#[cfg(feature = "serde")]
+pub(crate) use {grid::GridData, figure::FigureData};
\ No newline at end of file
@@ -0,0 +1,34 @@
+use crate::algebra::IVector;
+
+/// The result of trying to move or rotate a `Figure` on a `Grid`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Movement {
+    /// Whether the `Figure` was actually moved or rotated. If not, `position` and `rotation` are the unchanged ones.
+    pub success: bool,
+    /// Position of the `Figure` after the movement.
+    pub position: IVector,
+    /// Rotation state of the `Figure` after the movement.
+    pub rotation: i32,
+    /// The kick used so the `Figure` could fit, if it had to be kicked.
+    pub kick: Option<Kick>,
+}
+
+impl Movement {
+    /// Constructs a failed `Movement`, that leaves the `Figure` at `position` with `rotation`.
+    pub fn failed(position: IVector, rotation: i32) -> Self {
+        Self { success: false, position, rotation, kick: None }
+    }
+}
+
+/// An offset tested when rotating a `Figure`, taken from the kicks given by a `RotationSystem`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Kick {
+    /// Index of the kick in the list of kicks tested.
+    pub index: usize,
+    /// Offset added to the position of the `Figure`.
+    pub offset: IVector,
+    /// Whether it was the last kick of the list, which some spin rules reward.
+    pub last: bool,
+}
@@ -0,0 +1,67 @@
+use crate::algebra::IVector;
+use crate::piece::shape::Shape;
+
+/// Where and how a `Figure` spawns, relative to the default spawn of its `Shape` (see `Grid::spawn_position`).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Spawn {
+    /// Offset added to the default spawn position.
+    pub offset: IVector,
+    /// Rotation state the `Figure` spawns with.
+    pub rotation: i32,
+}
+
+/// The way figures spawn, for the shapes that don't spawn the default way. Shapes are compared independently of their
+/// rotation and position, so any `Shape` can be configured, not only tetrominoes.
+///
+/// ```
+/// use atris::algebra::Vector;
+/// use atris::grid::{Spawn, SpawnRules};
+/// use atris::piece::shape::Tetromino;
+///
+/// let mut rules = SpawnRules::new();
+/// rules.set(Tetromino::I.shape(), Spawn { offset: Vector(0, 1), rotation: 0 });
+/// assert_eq!(rules.get(&Tetromino::I.shape().rotated(1)).offset, Vector(0, 1));
+/// assert_eq!(rules.get(&Tetromino::T.shape()), Spawn::default());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpawnRules {
+    spawns: Vec<(Shape, Spawn)>,
+}
+
+impl SpawnRules {
+    /// Creates rules where every `Shape` spawns the default way.
+    pub fn new() -> Self {
+        Self {
+            spawns: Vec::new(),
+        }
+    }
+
+    /// Returns how figures with `shape` spawn.
+    pub fn get(&self, shape: &Shape) -> Spawn {
+        self.spawns.iter().find(|(s, _)| s == shape).map(|&(_, spawn)| spawn).unwrap_or_default()
+    }
+
+    /// Sets how figures with `shape` spawn, and returns how they spawned before, if it wasn't the default way.
+    pub fn set(&mut self, shape: Shape, spawn: Spawn) -> Option<Spawn> {
+        match self.spawns.iter_mut().find(|(s, _)| s == &shape) {
+            Some((_, old)) => Some(std::mem::replace(old, spawn)),
+            None => {
+                self.spawns.push((shape, spawn));
+                None
+            },
+        }
+    }
+
+    /// Returns the shapes that don't spawn the default way, with how they spawn.
+    pub fn iter(&self) -> impl Iterator<Item = &(Shape, Spawn)> {
+        self.spawns.iter()
+    }
+
+    /// Makes figures with `shape` spawn the default way again.
+    pub fn remove(&mut self, shape: &Shape) -> Option<Spawn> {
+        let i = self.spawns.iter().position(|(s, _)| s == shape)?;
+        Some(self.spawns.remove(i).1)
+    }
+}
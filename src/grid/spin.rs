@@ -0,0 +1,104 @@
+use crate::algebra::{Vector, IVector};
+use crate::piece::shape::Tetromino;
+use super::{Figure, Grid, Movement};
+
+/// The spin a `Figure` did, if it locked right after rotating into a tight spot.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Spin {
+    #[default]
+    None,
+    Mini,
+    Full,
+}
+
+/// Which spins are recognized.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpinRule {
+    /// No spins at all.
+    None,
+    /// Only T-spins, with the 3-corner rule.
+    #[default]
+    TSpin,
+    /// T-spins, with the 3-corner rule, and spins of any other `Shape` that can't move left, right nor up after
+    /// rotating. Those are minis, unless the rotation needed the last kick.
+    AllSpin,
+}
+
+impl SpinRule {
+    /// Returns the spin `fig` did, if its last successful action was `rotation`. For T-spins, with 3 of the 4 corners
+    /// around the center of the T occupied (or out of the grid), they are full if both corners the T points to are
+    /// occupied or the rotation needed the last kick, and mini otherwise.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::block::Block;
+    /// use atris::grid::{Figure, Grid, Spin, SpinRule};
+    /// use atris::piece::{shape::Tetromino, Piece};
+    /// use atris::blocktypes::standard::StandardType;
+    /// use atris::rotationsystems::SuperRotationSystem;
+    ///
+    /// let bt = StandardType{};
+    /// let srs = SuperRotationSystem::new();
+    /// let mut grid = Grid::new(Vector(10, 20));
+    /// // A T-spin double slot, with an overhang at (0, 2)
+    /// for x in (0..10).filter(|&x| x != 1) {
+    ///     grid.put(Vector(x, 0), Block::new(&bt));
+    /// }
+    /// for x in 3..10 {
+    ///     grid.put(Vector(x, 1), Block::new(&bt));
+    /// }
+    /// grid.put(Vector(0, 2), Block::new(&bt));
+    /// let mut fig = Figure::with_rotation_system(Piece::uniform(&bt, Tetromino::T.shape()), Vector(1, 1), &srs);
+    /// fig.set_rotation(3); // Pointing right
+    /// let rotation = grid.try_rotate(&mut fig, -1, &srs); // Pointing down, into the slot
+    /// assert!(rotation.success);
+    /// assert!(fig.cells().contains(&Vector(1, 0)));
+    /// assert_eq!(SpinRule::TSpin.detect(&grid, &fig, Some(&rotation)), Spin::Full);
+    /// assert_eq!(SpinRule::TSpin.detect(&grid, &fig, None), Spin::None);
+    /// ```
+    pub fn detect(&self, grid: &Grid, fig: &Figure, rotation: Option<&Movement>) -> Spin {
+        let Some(rotation) = rotation.filter(|rotation| rotation.success) else {
+            return Spin::None;
+        };
+        let last_kick = rotation.kick.is_some_and(|kick| kick.last);
+        match (self, Tetromino::of(fig.piece().shape())) {
+            (SpinRule::None, _) => Spin::None,
+            (_, Some(Tetromino::T)) => Self::t_spin(grid, fig, last_kick),
+            (SpinRule::TSpin, _) => Spin::None,
+            (SpinRule::AllSpin, _) => {
+                let immobile = [Vector(-1, 0), Vector(1, 0), Vector(0, 1)].into_iter()
+                    .all(|offset| !grid.fits_in(fig, offset, 0));
+                match (immobile, last_kick) {
+                    (false, _) => Spin::None,
+                    (true, false) => Spin::Mini,
+                    (true, true) => Spin::Full,
+                }
+            },
+        }
+    }
+
+    /// Checks the 3-corner rule of a T.
+    fn t_spin(grid: &Grid, fig: &Figure, last_kick: bool) -> Spin {
+        let shape = &fig.shape();
+        let neighbours = |p: IVector| [Vector(1, 0), Vector(0, 1), Vector(-1, 0), Vector(0, -1)].into_iter()
+            .filter(move |&d| shape.contains(&(p + d)));
+        let Some(&center) = shape.iter().find(|&&p| neighbours(p).count() == 3) else {
+            return Spin::None;
+        };
+        // The T points to the side whose opposite side is empty
+        let Some(front) = neighbours(center).find(|&d| !shape.contains(&(center - d))) else {
+            return Spin::None;
+        };
+        let side = Vector(-front.1, front.0);
+        let occupied = |offset: IVector| !grid.pos_available(fig.position + center + offset);
+        let front_corners = [front + side, front - side].into_iter().filter(|&c| occupied(c)).count();
+        let back_corners = [-front + side, -front - side].into_iter().filter(|&c| occupied(c)).count();
+        match (front_corners + back_corners >= 3, front_corners == 2 || last_kick) {
+            (false, _) => Spin::None,
+            (true, true) => Spin::Full,
+            (true, false) => Spin::Mini,
+        }
+    }
+}
@@ -0,0 +1,890 @@
+use std::{collections::{BTreeMap, BTreeSet, HashSet}, hash::Hasher};
+
+use rand::{rngs::StdRng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::{grid::{Grid, Figure, Movement}, algebra::{Vector, UVector}, block::state::State};
+use crate::{gamemode::GameMode, physics::Physics, piece::{Piece, shape::Shape}, piecequeue::PieceQueue};
+use crate::{scoringrule::{Lock, ScoringRule}, attack::Attack, gridsession::{GridConfig, GridSeeds}};
+use crate::{garbage::{GarbageGenerator, GarbageQueue}, replay::Fnv1a};
+use crate::blocktypes::rock::RockType;
+use crate::{block::Block, event::{SessionEvent, GridEvent, GameOver}, input::{Action, AutoShift, HandlingConfig, PlayerInput}};
+#[cfg(feature = "serde")]
+use crate::{blocktypes::BlockTypes, grid::{GridData, FigureData}, piece::PieceData, snapshot::Unresolved};
+
+/// State of a player in a `GridEngine`.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+struct GridPlayer<'a> {
+    figure: Option<Figure<'a>>,
+    physics: Physics,
+    /// Ticks left until the next `Figure` spawns.
+    entry_delay: u32,
+    handling: HandlingConfig,
+    /// Inputs received since the last tick.
+    inputs: Vec<PlayerInput>,
+    shift: AutoShift,
+    /// Keys currently held, other than the horizontal ones.
+    held_keys: HashSet<Action>,
+    hold: Option<Piece<'a>>,
+    /// Whether the player already held since their last `Figure` locked.
+    hold_used: bool,
+    /// The rotation of the `Figure`, if it was its last successful action, to detect spins.
+    last_rotation: Option<Movement>,
+    /// The ghost of the `Figure` last sent to the session.
+    ghost: Option<Shape>,
+}
+
+/// Full rows waiting for the clear delay to be over.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct LineClear {
+    rows: Vec<usize>,
+    timer: u32,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GridEngine<'a> {
+    id: usize,
+    grid: Grid<'a>,
+    // blocks: Vec<PositionedBlock<'a>>,
+    players: BTreeMap<usize, GridPlayer<'a>>,
+    queue: PieceQueue<'a>,
+    line_clear: Option<LineClear>,
+    cleared_rows: Vec<usize>,
+    // Configurations:
+    #[cfg_attr(feature = "serde", serde(skip))]
+    game_mode: &'a dyn GameMode<StdRng>,
+    config: GridConfig,
+    // State:
+    state: State<u64>,
+    seeds: GridSeeds,
+    /// Not serialized: it's restored by generating the pieces of the queue again.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    piece_rng: StdRng,
+    /// Same as a `StdRng`, but it can be serialized.
+    rng: ChaCha12Rng,
+    running: bool,
+    paused: bool,
+    game_over: Option<GameOver>,
+    scoring_rule: Box<dyn ScoringRule>,
+    score: u64,
+    lines: u64,
+    level: u32,
+    /// Ticks played so far, for the levels that advance with time.
+    ticks: u64,
+    attack: Attack,
+    /// Height of the stack, in rows.
+    height: usize,
+    opponents: BTreeSet<usize>,
+    garbage: GarbageQueue,
+    garbage_generator: GarbageGenerator,
+    /// Events produced since the last step.
+    events: Vec<GridEvent>,
+}
+
+impl<'a> GridEngine<'a> {
+    /// Creates a grid. If the game mode has a `LevelCurve`, the grid starts at its first level, and the gravity and
+    /// delays of the physics of `config` follow the curve.
+    pub fn new(grid_id: usize, grid_bounds: UVector, game_mode: &'a dyn GameMode<StdRng>, mut config: GridConfig,
+            seeds: GridSeeds) -> Self {
+        let level = game_mode.level_curve().map_or(1, |curve| curve.start_level());
+        if let Some(curve) = game_mode.level_curve() {
+            curve.speed(level).apply(&mut config.physics, config.tps);
+        }
+        let scoring_rule = game_mode.scoring_rule();
+        // Scoring and attack agree on which clears are back-to-backs
+        let mut attack = Attack::new(config.attack.clone());
+        attack.set_difficult_lines(scoring_rule.difficult_lines());
+        let garbage = GarbageQueue::new(config.garbage_delay);
+        let garbage_generator = GarbageGenerator::new(config.garbage_style);
+        Self {
+            id: grid_id,
+            grid: Grid::with_hidden_rows(grid_bounds, config.hidden_rows),
+            players: BTreeMap::new(),
+            queue: PieceQueue::new(config.preview, game_mode.randomizer()),
+            line_clear: None,
+            cleared_rows: Vec::new(),
+            game_mode,
+            config,
+            state: State::new(),
+            seeds,
+            piece_rng: StdRng::seed_from_u64(seeds.pieces),
+            rng: ChaCha12Rng::seed_from_u64(seeds.grid),
+            running: false,
+            paused: false,
+            game_over: None,
+            scoring_rule,
+            score: 0,
+            lines: 0,
+            level,
+            ticks: 0,
+            attack,
+            height: 0,
+            opponents: BTreeSet::new(),
+            garbage,
+            garbage_generator,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn id(&self) -> usize { self.id }
+
+    pub fn grid(&self) -> &Grid<'a> { &self.grid }
+
+    pub fn figure(&self, player_id: usize) -> Option<&Figure<'a>> { self.players.get(&player_id)?.figure.as_ref() }
+
+    pub fn state(&self) -> State<u64> { self.state }
+
+    /// Returns the game mode the grid is played with.
+    pub fn game_mode(&self) -> &'a dyn GameMode<StdRng> { self.game_mode }
+
+    /// Returns the configuration of the grid, with the physics of its current level.
+    pub fn config(&self) -> &GridConfig { &self.config }
+
+    /// Returns the seeds the grid was created with.
+    pub fn seeds(&self) -> GridSeeds { self.seeds }
+
+    /// Returns the pieces that will spawn next in the grid.
+    pub fn queue(&self) -> &PieceQueue<'a> { &self.queue }
+
+    /// Returns the absolute positions of the blocks of the ghost of a player's `Figure`: where it would land if it was
+    /// hard dropped.
+    pub fn ghost(&self, player_id: usize) -> Option<Shape> {
+        Some(self.grid.ghost_cells(self.figure(player_id)?))
+    }
+
+    /// Returns the piece a player is holding, if any.
+    pub fn hold_piece(&self, player_id: usize) -> Option<&Piece<'a>> { self.players.get(&player_id)?.hold.as_ref() }
+
+    /// Returns the rows waiting to be cleared, in ascending order, if there are any.
+    pub fn pending_clear_rows(&self) -> Option<&[usize]> { Some(&self.line_clear.as_ref()?.rows) }
+
+    /// Returns the rows deleted in the last line clear, in ascending order (as they were before being deleted).
+    pub fn cleared_rows(&self) -> &[usize] { &self.cleared_rows }
+
+    /// Checks whether the game is running (even if paused).
+    pub fn is_running(&self) -> bool { self.running }
+
+    /// Checks whether the game is paused.
+    pub fn is_paused(&self) -> bool { self.paused }
+
+    /// Returns the points scored in the grid.
+    pub fn score(&self) -> u64 { self.score }
+
+    /// Returns the number of lines cleared in the grid.
+    pub fn lines(&self) -> u64 { self.lines }
+
+    pub fn level(&self) -> u32 { self.level }
+
+    /// Returns why the game is over, if it is.
+    pub fn game_over(&self) -> Option<GameOver> { self.game_over }
+
+    /// Returns the ids of the other grids of the session.
+    pub fn opponents(&self) -> &BTreeSet<usize> { &self.opponents }
+
+    /// Returns the lines of garbage received and not yet put in the grid.
+    pub fn incoming_garbage(&self) -> u32 { self.garbage.lines() }
+
+    /// Returns the garbage received and not yet put in the grid.
+    pub fn garbage(&self) -> &GarbageQueue { &self.garbage }
+
+    /// Returns the attack of the grid, with its combo and back-to-back.
+    pub fn attack(&self) -> &Attack { &self.attack }
+
+    /// Adds a player to the grid, handling their inputs with `handling`. Their first `Figure` spawns on the next tick.
+    pub fn add_player(&mut self, player_id: usize, handling: HandlingConfig) {
+        self.players.entry(player_id).or_default().handling = handling;
+    }
+
+    /// Changes how the inputs of a player are handled.
+    pub fn set_handling(&mut self, player_id: usize, handling: HandlingConfig) {
+        if let Some(player) = self.players.get_mut(&player_id) {
+            player.handling = handling;
+        }
+    }
+
+    /// Moves the `Figure` of a player horizontally, if it fits. Returns whether it was moved.
+    pub fn try_move(&mut self, player_id: usize, x_offset: i32) -> bool {
+        let Some(GridPlayer { figure: Some(fig), physics, last_rotation, .. }) = self.players.get_mut(&player_id) else {
+            return false;
+        };
+        let moved = self.grid.try_move(fig, Vector(x_offset, 0)).success;
+        if moved {
+            physics.on_moved(&self.config.physics, fig);
+            *last_rotation = None;
+        }
+        moved
+    }
+
+    /// Rotates the `Figure` of a player `angle` quarter turns counter-clockwise, if it fits. Returns whether it was
+    /// rotated.
+    pub fn try_rotate(&mut self, player_id: usize, angle: i32) -> bool {
+        let Some(GridPlayer { figure: Some(fig), physics, last_rotation, .. }) = self.players.get_mut(&player_id) else {
+            return false;
+        };
+        let rotation = self.grid.try_rotate(fig, angle, self.game_mode.rotation_system());
+        if rotation.success {
+            physics.on_moved(&self.config.physics, fig);
+            *last_rotation = Some(rotation);
+        }
+        rotation.success
+    }
+
+    /// Starts or stops soft dropping the `Figure`s of a player, with their soft drop factor.
+    pub fn soft_drop(&mut self, player_id: usize, soft_drop: bool) {
+        if let Some(player) = self.players.get_mut(&player_id) {
+            player.physics.set_soft_drop(soft_drop.then_some(player.handling.soft_drop_factor));
+        }
+    }
+
+    /// Drops the `Figure` of a player to the ground and locks it.
+    pub fn hard_drop(&mut self, player_id: usize) {
+        let Some(GridPlayer { figure: Some(fig), physics, last_rotation, .. }) = self.players.get_mut(&player_id) else {
+            return;
+        };
+        let cells = physics.hard_drop(&self.grid, fig);
+        if cells > 0 {
+            *last_rotation = None;
+        }
+        self.score += self.scoring_rule.on_drop(cells as u32, true, self.level);
+        self.lock(player_id);
+    }
+
+    /// Puts the `Figure` of a player in their hold slot, and spawns the piece that was there before, or the next one of
+    /// the queue. Returns whether it could be done, which is only once until the next `Figure` locks.
+    pub fn hold(&mut self, player_id: usize) -> bool {
+        if !self.swap_hold(player_id) {
+            return false;
+        }
+        self.check_block_out(player_id);
+        true
+    }
+
+    /// Holds like `hold`, without checking whether the new `Figure` overlaps the blocks of the grid.
+    fn swap_hold(&mut self, player_id: usize) -> bool {
+        let Some(player) = self.players.get_mut(&player_id) else {
+            return false;
+        };
+        if !self.config.hold || player.hold_used {
+            return false;
+        }
+        let Some(fig) = player.figure.take() else {
+            return false;
+        };
+        player.hold_used = true;
+        let held = fig.piece().shape().clone();
+        let piece = match player.hold.replace(fig.piece().clone()) {
+            Some(piece) => piece,
+            None => self.next_piece(),
+        };
+        self.send(GridEvent::Hold(player_id, held));
+        self.spawn_piece(player_id, piece);
+        true
+    }
+
+    /// Queues an input of a player, to be handled on the next tick.
+    pub fn input(&mut self, player_id: usize, input: PlayerInput) {
+        if let Some(player) = self.players.get_mut(&player_id) {
+            player.inputs.push(input);
+        }
+    }
+
+    /// Handles the inputs a player queued since the last tick, after shifting their `Figure` if a horizontal key is
+    /// held long enough.
+    fn handle_inputs(&mut self, player_id: usize) {
+        let Some(player) = self.players.get_mut(&player_id) else {
+            return;
+        };
+        let shifts = player.shift.tick(&player.handling);
+        let direction = player.shift.direction();
+        let inputs = std::mem::take(&mut player.inputs);
+        for _ in 0..shifts {
+            if !self.try_move(player_id, direction) {
+                break;
+            }
+        }
+        for input in inputs {
+            if !self.running {
+                break;
+            }
+            self.handle_input(player_id, input);
+        }
+    }
+
+    /// Applies an input of a player to their `Figure`.
+    fn handle_input(&mut self, player_id: usize, input: PlayerInput) {
+        let Some(player) = self.players.get_mut(&player_id) else {
+            return;
+        };
+        match input {
+            PlayerInput::Press(action) => {player.held_keys.insert(action);},
+            PlayerInput::Release(action) => {player.held_keys.remove(&action);},
+        }
+        match input {
+            PlayerInput::Press(Action::Left) => if player.shift.press(-1) {
+                self.try_move(player_id, -1);
+            },
+            PlayerInput::Press(Action::Right) => if player.shift.press(1) {
+                self.try_move(player_id, 1);
+            },
+            PlayerInput::Release(Action::Left) => player.shift.release(-1),
+            PlayerInput::Release(Action::Right) => player.shift.release(1),
+            PlayerInput::Press(Action::SoftDrop) => self.soft_drop(player_id, true),
+            PlayerInput::Release(Action::SoftDrop) => self.soft_drop(player_id, false),
+            PlayerInput::Press(Action::HardDrop) => self.hard_drop(player_id),
+            PlayerInput::Press(Action::RotateCW) => {self.try_rotate(player_id, -1);},
+            PlayerInput::Press(Action::RotateCCW) => {self.try_rotate(player_id, 1);},
+            PlayerInput::Press(Action::Rotate180) => {self.try_rotate(player_id, 2);},
+            PlayerInput::Press(Action::Hold) => {self.hold(player_id);},
+            PlayerInput::Release(_) => (),
+        }
+    }
+
+    /// Handles a message of the session. Inputs are queued, to be handled on the next step.
+    pub fn handle(&mut self, e: SessionEvent) {
+        match e {
+            SessionEvent::Start => self.start(),
+            SessionEvent::Pause => self.paused = true,
+            SessionEvent::Resume => self.paused = false,
+            SessionEvent::Stop => self.running = false,
+            SessionEvent::PlayerAdded(player_id, handling) => self.add_player(player_id, handling),
+            SessionEvent::Handling(player_id, handling) => self.set_handling(player_id, handling),
+            // Inputs received while paused are handled after resuming, so no key release is missed
+            SessionEvent::Input(player_id, input) => self.input(player_id, input),
+            SessionEvent::GridAdded(grid_id) => {self.opponents.insert(grid_id);},
+            SessionEvent::GridRemoved(grid_id) => {self.opponents.remove(&grid_id);},
+            SessionEvent::Garbage(lines) => self.garbage.push(lines),
+            // The timing of the ticks is up to whoever steps the engine
+            SessionEvent::Speed(_) | SessionEvent::FrameStepping(_) | SessionEvent::StepFrame => (),
+        }
+    }
+
+    /// Records an event, to be returned by the next step.
+    fn send(&mut self, e: GridEvent) {
+        self.events.push(e);
+    }
+
+    /// Starts the game, unless it's already over. The queue is filled so its pieces can be previewed.
+    pub fn start(&mut self) {
+        if self.game_over.is_none() {
+            self.running = true;
+            self.queue.fill(self.game_mode, &mut self.piece_rng);
+            self.send_queue();
+        }
+    }
+
+    /// Advances the grid exactly one tick: handles the messages given, in order, then steps the physics if the game is
+    /// running and not paused. Returns the events produced since the last step. Nothing depends on the wall clock, so
+    /// the same messages always give the same events.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::event::{GridEvent, SessionEvent};
+    /// use atris::gamemode::GameMode;
+    /// use atris::gamemodes::standard::StandardMode;
+    /// use atris::gridengine::GridEngine;
+    /// use atris::gridsession::{GridConfig, GridSeeds};
+    /// use atris::input::{Action, HandlingConfig, PlayerInput};
+    /// use rand::rngs::StdRng;
+    ///
+    /// let mut game_mode = StandardMode::new();
+    /// GameMode::<StdRng>::initialize(&mut game_mode);
+    /// let seeds = GridSeeds { pieces: 1, grid: 2 };
+    /// let mut engine = GridEngine::new(1, Vector(10, 20), &game_mode, GridConfig::default(), seeds);
+    /// engine.step([SessionEvent::PlayerAdded(0, HandlingConfig::default()), SessionEvent::Start]);
+    /// assert!(engine.figure(0).is_some());
+    /// let events = engine.step([SessionEvent::Input(0, PlayerInput::Press(Action::HardDrop))]);
+    /// assert!(matches!(events[0], GridEvent::Locked(_)));
+    /// assert_eq!(engine.ticks(), 2);
+    /// ```
+    pub fn step(&mut self, messages: impl IntoIterator<Item = SessionEvent>) -> Vec<GridEvent> {
+        for e in messages {
+            self.handle(e);
+        }
+        if self.running && !self.paused {
+            self.physics_step();
+        }
+        self.update_ghosts();
+        std::mem::take(&mut self.events)
+    }
+
+    /// Returns the ticks played so far.
+    pub fn ticks(&self) -> u64 { self.ticks }
+
+    /// Returns a checksum of the state of the game: the blocks of the grid, the figures, held and next pieces of the
+    /// players, the garbage waiting, the score, lines, level, ticks played and whether the game is over. Grids that
+    /// played the same have the same checksum, on any platform.
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        let shape = |hasher: &mut Fnv1a, shape: &Shape| {
+            hasher.write_u64(shape.len() as u64);
+            for pos in shape.iter() {
+                hasher.write_i32(pos.0);
+                hasher.write_i32(pos.1);
+            }
+        };
+        for row in self.grid.iter() {
+            for cell in row.iter() {
+                match cell {
+                    Some(block) => {
+                        hasher.write(block.block_type.id().as_bytes());
+                        hasher.write_u64(block.state.value());
+                    },
+                    None => hasher.write_u8(0),
+                }
+            }
+        }
+        for (&player_id, player) in self.players.iter() {
+            hasher.write_u64(player_id as u64);
+            hasher.write_u32(player.entry_delay);
+            hasher.write_u8(player.figure.is_some() as u8 | (player.hold.is_some() as u8) << 1);
+            if let Some(fig) = player.figure.as_ref() {
+                hasher.write_i32(fig.position.0);
+                hasher.write_i32(fig.position.1);
+                hasher.write_i32(fig.rotation());
+                shape(&mut hasher, fig.piece().shape());
+            }
+            if let Some(piece) = player.hold.as_ref() {
+                shape(&mut hasher, piece.shape());
+            }
+        }
+        for piece in self.queue.previews() {
+            shape(&mut hasher, piece.shape());
+        }
+        for garbage in self.garbage.pending() {
+            hasher.write_u32(garbage.lines);
+            hasher.write_u32(garbage.timer);
+        }
+        hasher.write_u64(self.score);
+        hasher.write_u64(self.lines);
+        hasher.write_u32(self.level);
+        hasher.write_u64(self.ticks);
+        hasher.write_u8(self.game_over.map_or(0, |reason| reason as u8 + 1));
+        hasher.finish()
+    }
+
+    /// Advances the physics of the grid one tick: clears the full rows once the clear delay is over, and for every
+    /// player, spawns their `Figure` once the entry delay is over, handles their inputs, makes the `Figure` fall and
+    /// locks it if it has to.
+    fn physics_step(&mut self) {
+        self.ticks += 1;
+        self.garbage.tick();
+        self.update_level();
+        if let Some(line_clear) = self.line_clear.as_mut() {
+            line_clear.timer = line_clear.timer.saturating_sub(1);
+            if line_clear.timer == 0 {
+                self.clear_rows();
+            }
+        }
+        let player_ids: Vec<usize> = self.players.keys().copied().collect();
+        for player_id in player_ids {
+            if !self.running {
+                return;
+            }
+            let player = self.players.get_mut(&player_id).unwrap();
+            if player.figure.is_none() {
+                player.entry_delay = player.entry_delay.saturating_sub(1);
+                if player.entry_delay == 0 && self.line_clear.is_none() {
+                    self.spawn(player_id);
+                }
+            }
+            // Inputs are handled even without a `Figure`, so the DAS keeps charging
+            self.handle_inputs(player_id);
+            let Some(GridPlayer { figure: Some(fig), physics, last_rotation, .. }) = self.players.get_mut(&player_id)
+            else {
+                continue;
+            };
+            let row = fig.position.1;
+            let locked = physics.step(&self.config.physics, &self.grid, fig);
+            if fig.position.1 != row {
+                *last_rotation = None;
+            }
+            if physics.soft_drop().is_some() {
+                let cells = (row - fig.position.1) as u32;
+                self.score += self.scoring_rule.on_drop(cells, false, self.level);
+            }
+            if locked {
+                self.lock(player_id);
+            }
+        }
+    }
+
+    /// Spawns the next piece of the queue as the `Figure` of a player. With the IHS or the IRS, the keys the player is
+    /// holding are applied right away, before checking for a block out, so they can avoid it.
+    fn spawn(&mut self, player_id: usize) {
+        let piece = self.next_piece();
+        self.spawn_piece(player_id, piece);
+        let Some(player) = self.players.get(&player_id) else {
+            return;
+        };
+        let held = |action| player.held_keys.contains(&action) as i32;
+        let angle = held(Action::RotateCCW) - held(Action::RotateCW) + 2 * held(Action::Rotate180);
+        let hold = held(Action::Hold) == 1;
+        if self.config.ihs && hold {
+            self.swap_hold(player_id);
+        }
+        // A rotation at spawn doesn't make the next lock a spin
+        if self.config.irs && angle != 0 && self.try_rotate(player_id, angle) {
+            if let Some(player) = self.players.get_mut(&player_id) {
+                player.last_rotation = None;
+            }
+        }
+        self.check_block_out(player_id);
+    }
+
+    /// Ends the game if the `Figure` of a player overlaps the blocks of the grid (block out).
+    fn check_block_out(&mut self, player_id: usize) {
+        let Some(fig) = self.players.get(&player_id).and_then(|player| player.figure.as_ref()) else {
+            return;
+        };
+        if self.config.game_over.block_out && !self.grid.fits_in(fig, Vector(0, 0), 0) {
+            self.end_game(GameOver::BlockOut);
+        }
+    }
+
+    /// Takes the next piece of the queue, and tells the session the pieces now waiting.
+    fn next_piece(&mut self) -> Piece<'a> {
+        let piece = self.queue.next(self.game_mode, &mut self.piece_rng);
+        self.send_queue();
+        piece
+    }
+
+    /// Tells the session the shapes of the pieces that will spawn next.
+    fn send_queue(&mut self) {
+        let shapes = self.queue.previews().map(|piece| piece.shape().clone()).collect();
+        self.send(GridEvent::Queue(shapes));
+    }
+
+    /// Spawns `piece` as the `Figure` of a player, above the visible rows, as the spawn rules say. It may overlap the
+    /// blocks of the grid: the callers check for a block out.
+    fn spawn_piece(&mut self, player_id: usize, piece: Piece<'a>) {
+        let game_mode = self.game_mode;
+        let mut fig = Figure::with_rotation_system(piece, Vector(0, 0), game_mode.rotation_system());
+        let spawn = self.config.spawn.get(fig.piece().shape());
+        fig.set_rotation(spawn.rotation);
+        fig.position = self.grid.spawn_position(&fig.shape()) + spawn.offset;
+        let player = self.players.entry(player_id).or_default();
+        player.figure = Some(fig);
+        player.last_rotation = None;
+        // A new `Figure` starts falling from scratch, but keeps being soft dropped
+        let soft_drop = player.physics.soft_drop();
+        player.physics = Physics::new();
+        player.physics.set_soft_drop(soft_drop);
+    }
+
+    /// Copies the blocks of the `Figure` of a player into the grid, and starts the line clear if it filled some rows.
+    /// The player gets a new `Figure` after the entry delay.
+    fn lock(&mut self, player_id: usize) -> Vec<UVector> {
+        let Some(player) = self.players.get_mut(&player_id) else {
+            return Vec::new();
+        };
+        let Some(fig) = player.figure.take() else {
+            return Vec::new();
+        };
+        let lock_out = fig.cells().iter().all(|p| p.1 >= self.grid.visible_rows() as i32);
+        let spin = self.config.spin_rule.detect(&self.grid, &fig, player.last_rotation.as_ref());
+        let placed = self.grid.place(&fig);
+        player.hold_used = false;
+        if self.config.game_over.lock_out && lock_out {
+            self.end_game(GameOver::LockOut);
+            return placed;
+        }
+        let physics = &self.config.physics;
+        player.entry_delay = physics.are;
+        let full_rows = self.grid.full_rows();
+        let pending_rows = self.line_clear.as_ref().map_or(0, |line_clear| line_clear.rows.len());
+        let lock = Lock {
+            lines: full_rows.len().saturating_sub(pending_rows),
+            spin,
+            perfect_clear: !full_rows.is_empty() && (0..self.grid.n_rows()).all(|i| {
+                self.grid.full_row(i) || self.grid.empty_row(i)
+            }),
+            level: self.level,
+        };
+        self.score += self.scoring_rule.on_lock(&lock);
+        let attack = self.attack.on_lock(&lock);
+        self.events.push(GridEvent::Locked(lock));
+        if !full_rows.is_empty() {
+            player.entry_delay += physics.clear_delay;
+            match self.line_clear.as_mut() {
+                Some(line_clear) => line_clear.rows = full_rows,
+                None => self.line_clear = Some(LineClear { rows: full_rows, timer: physics.clear_delay }),
+            }
+            if physics.clear_delay == 0 {
+                self.clear_rows();
+            }
+        }
+        // Clearing rows cancels the garbage received before sending any, and not clearing them lets it in
+        if lock.lines > 0 {
+            let sent = self.garbage.cancel(attack);
+            if sent > 0 {
+                self.send(GridEvent::Garbage(sent));
+            }
+        } else {
+            self.receive_garbage();
+        }
+        self.update_height();
+        placed
+    }
+
+    /// Puts the garbage whose delay is over into the grid, up to the garbage cap, as rows of rocks with holes following
+    /// the garbage style.
+    fn receive_garbage(&mut self) {
+        static ROCK: RockType = RockType{};
+        let n_cols = self.grid.n_cols();
+        let mut rows = Vec::new();
+        for lines in self.garbage.take_ready(self.config.garbage_cap) {
+            let mut garbage = self.garbage_generator.rows(lines, n_cols, &ROCK, &mut self.rng);
+            // Older garbage ends up above
+            garbage.append(&mut rows);
+            rows = garbage;
+        }
+        if !rows.is_empty() {
+            self.raise(rows);
+        }
+    }
+
+    /// Inserts rows (usually garbage) at the bottom of the grid, pushing the blocks and the figures up. The game is over
+    /// if blocks are pushed past the top (top out).
+    pub fn raise(&mut self, rows: Vec<Vec<Option<Block<'a>>>>) {
+        let n = rows.len();
+        let topped_out = self.grid.raise(rows);
+        if let Some(line_clear) = self.line_clear.as_mut() {
+            let n_rows = self.grid.n_rows();
+            line_clear.rows = line_clear.rows.iter().map(|i| i + n).filter(|&i| i < n_rows).collect();
+        }
+        for fig in self.players.values_mut().filter_map(|player| player.figure.as_mut()) {
+            for _ in 0..n {
+                if self.grid.fits_in(fig, Vector(0, 0), 0) {
+                    break;
+                }
+                fig.position = fig.position + Vector(0, 1);
+            }
+        }
+        self.update_height();
+        if self.config.game_over.top_out && topped_out {
+            self.end_game(GameOver::TopOut);
+        }
+    }
+
+    /// Tells the session the height of the stack if it changed.
+    fn update_height(&mut self) {
+        let height = self.grid.last_non_empty_row().map_or(0, |row| row + 1);
+        if height != self.height {
+            self.height = height;
+            self.send(GridEvent::Height(height));
+        }
+    }
+
+    /// Tells the session the ghosts of the figures that moved, rotated, spawned or locked since the last step.
+    fn update_ghosts(&mut self) {
+        for (&player_id, player) in self.players.iter_mut() {
+            let ghost = player.figure.as_ref().map(|fig| self.grid.ghost_cells(fig));
+            // `Shape`s compare regardless of their position, so their cells are compared instead
+            if ghost.as_ref().map(Shape::positions) != player.ghost.as_ref().map(Shape::positions) {
+                player.ghost = ghost.clone();
+                self.events.push(GridEvent::Ghost(player_id, ghost));
+            }
+        }
+    }
+
+    /// Stops the game, and tells the session why the game is over.
+    fn end_game(&mut self, reason: GameOver) {
+        self.game_over = Some(reason);
+        self.running = false;
+        self.send(GridEvent::GameOver(reason));
+    }
+
+    /// Deletes the rows waiting to be cleared, making the rows above them fall.
+    fn clear_rows(&mut self) {
+        if let Some(line_clear) = self.line_clear.take() {
+            self.cleared_rows = self.grid.delete_rows(&line_clear.rows);
+            self.lines += self.cleared_rows.len() as u64;
+            self.send(GridEvent::LinesCleared(self.cleared_rows.clone()));
+            self.update_height();
+            self.update_level();
+        }
+    }
+
+    /// Updates the level from the lines cleared and the time played, following the `LevelCurve` of the game mode, and
+    /// sets the physics to the speed of the new level.
+    fn update_level(&mut self) {
+        let Some(curve) = self.game_mode.level_curve() else {
+            return;
+        };
+        let level = curve.level(self.lines, self.ticks * 60 / self.config.tps.max(1));
+        if level != self.level {
+            self.level = level;
+            curve.speed(level).apply(&mut self.config.physics, self.config.tps);
+            self.send(GridEvent::Level(level));
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a> GridEngine<'a> {
+    /// Restores a grid from a snapshot: a `GridEngine` serialized while playing with `game_mode`. Its block types are
+    /// resolved through `block_types`. The grid goes on exactly as the one saved would have.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::blocktypes::BlockTypes;
+    /// use atris::event::SessionEvent;
+    /// use atris::gamemode::GameMode;
+    /// use atris::gamemodes::standard::StandardMode;
+    /// use atris::gridengine::GridEngine;
+    /// use atris::gridsession::{GridConfig, GridSeeds};
+    /// use atris::input::{Action, HandlingConfig, PlayerInput};
+    /// use rand::rngs::StdRng;
+    ///
+    /// let mut game_mode = StandardMode::new();
+    /// GameMode::<StdRng>::initialize(&mut game_mode);
+    /// let seeds = GridSeeds { pieces: 1, grid: 2 };
+    /// let mut engine = GridEngine::new(1, Vector(10, 20), &game_mode, GridConfig::default(), seeds);
+    /// engine.step([SessionEvent::PlayerAdded(0, HandlingConfig::default()), SessionEvent::Start]);
+    /// engine.step([SessionEvent::Input(0, PlayerInput::Press(Action::HardDrop))]);
+    /// let snapshot = serde_json::to_string(&engine).unwrap();
+    /// let block_types = BlockTypes::base_types();
+    /// let mut deserializer = serde_json::Deserializer::from_str(&snapshot);
+    /// let mut restored = GridEngine::restore(&mut deserializer, &game_mode, &block_types).unwrap();
+    /// assert_eq!(restored.checksum(), engine.checksum());
+    /// let drop = [SessionEvent::Input(0, PlayerInput::Press(Action::HardDrop))];
+    /// assert_eq!(restored.step(drop.clone()), engine.step(drop));
+    /// assert_eq!(restored.checksum(), engine.checksum());
+    /// ```
+    pub fn restore<'de, D: serde::Deserializer<'de>>(deserializer: D, game_mode: &'a dyn GameMode<StdRng>,
+            block_types: &'a BlockTypes) -> Result<Self, D::Error> {
+        use serde::{de::Error, Deserialize};
+        EngineData::deserialize(deserializer)?.restore(game_mode, block_types).map_err(D::Error::custom)
+    }
+}
+
+/// Serialized form of a `GridPlayer`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct PlayerData {
+    figure: Option<FigureData>,
+    physics: Physics,
+    entry_delay: u32,
+    handling: HandlingConfig,
+    inputs: Vec<PlayerInput>,
+    shift: AutoShift,
+    held_keys: HashSet<Action>,
+    hold: Option<PieceData>,
+    hold_used: bool,
+    last_rotation: Option<Movement>,
+    ghost: Option<Shape>,
+}
+
+/// Serialized form of a `PieceQueue`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct QueueData {
+    preview: usize,
+    randomizer: String,
+    generated: u64,
+    pending: usize,
+}
+
+/// Serialized form of a `ScoringRule`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct ScoringRuleData {
+    id: String,
+    state: Vec<u64>,
+}
+
+/// Serialized form of a `GridEngine`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct EngineData {
+    id: usize,
+    grid: GridData,
+    players: BTreeMap<usize, PlayerData>,
+    queue: QueueData,
+    line_clear: Option<LineClear>,
+    cleared_rows: Vec<usize>,
+    config: GridConfig,
+    state: State<u64>,
+    seeds: GridSeeds,
+    rng: ChaCha12Rng,
+    running: bool,
+    paused: bool,
+    game_over: Option<GameOver>,
+    scoring_rule: ScoringRuleData,
+    score: u64,
+    lines: u64,
+    level: u32,
+    ticks: u64,
+    attack: Attack,
+    height: usize,
+    opponents: BTreeSet<usize>,
+    garbage: GarbageQueue,
+    garbage_generator: GarbageGenerator,
+    events: Vec<GridEvent>,
+}
+
+#[cfg(feature = "serde")]
+impl EngineData {
+    fn restore<'a>(self, game_mode: &'a dyn GameMode<StdRng>, block_types: &'a BlockTypes)
+            -> Result<GridEngine<'a>, String> {
+        let mut players = BTreeMap::new();
+        for (player_id, player) in self.players {
+            players.insert(player_id, GridPlayer {
+                figure: player.figure.resolve(block_types)?,
+                physics: player.physics,
+                entry_delay: player.entry_delay,
+                handling: player.handling,
+                inputs: player.inputs,
+                shift: player.shift,
+                held_keys: player.held_keys,
+                hold: player.hold.resolve(block_types)?,
+                hold_used: player.hold_used,
+                last_rotation: player.last_rotation,
+                ghost: player.ghost,
+            });
+        }
+        // The pieces of the queue are generated again, leaving its randomizer and the pieces' rng as they were
+        let randomizer = game_mode.randomizer();
+        if randomizer.id() != self.queue.randomizer {
+            return Err(format!("snapshot of randomizer {:?}, not {:?}", self.queue.randomizer, randomizer.id()));
+        }
+        let mut queue = PieceQueue::new(self.queue.preview, randomizer);
+        let mut piece_rng = StdRng::seed_from_u64(self.seeds.pieces);
+        queue.regenerate(game_mode, &mut piece_rng, self.queue.generated, self.queue.pending);
+        let mut scoring_rule = game_mode.scoring_rule();
+        if scoring_rule.id() != self.scoring_rule.id || !scoring_rule.set_state(&self.scoring_rule.state) {
+            return Err(format!("invalid snapshot of scoring rule {:?}", self.scoring_rule.id));
+        }
+        Ok(GridEngine {
+            id: self.id,
+            grid: self.grid.resolve(block_types)?,
+            players,
+            queue,
+            line_clear: self.line_clear,
+            cleared_rows: self.cleared_rows,
+            game_mode,
+            config: self.config,
+            state: self.state,
+            seeds: self.seeds,
+            piece_rng,
+            rng: self.rng,
+            running: self.running,
+            paused: self.paused,
+            game_over: self.game_over,
+            scoring_rule,
+            score: self.score,
+            lines: self.lines,
+            level: self.level,
+            ticks: self.ticks,
+            attack: self.attack,
+            height: self.height,
+            opponents: self.opponents,
+            garbage: self.garbage,
+            garbage_generator: self.garbage_generator,
+            events: self.events,
+        })
+    }
+}
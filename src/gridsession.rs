@@ -1,72 +1,219 @@
-use std::{collections::HashMap, time::{Duration, Instant}, thread::sleep};
+use crossbeam::channel::{Sender, Receiver, TryRecvError};
+use rand::rngs::StdRng;
 
-use crossbeam::channel::{Sender, Receiver};
-use rand::{rngs::ThreadRng, thread_rng};
+use crate::{grid::{SpawnRules, SpinRule}, algebra::{UVector, Vector}, gamemode::GameMode, physics::PhysicsConfig};
+use crate::{attack::AttackTable, garbage::GarbageStyle, gridengine::GridEngine, clock::{Clock, RealClock, Ticker}};
+use crate::{event::{SessionEvent, GridEvent}, replay::GridReplay};
 
-use crate::{grid::Grid, grid::Figure, algebra::UVector, block::state::State};
+/// Configuration of a `GridSession`, shared by all the grids of an `AtrisSession`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridConfig {
+    pub tps: u64, // Ticks per Second, for the loop
+    pub physics: PhysicsConfig,
+    /// Rows above the visible ones, where figures spawn.
+    pub hidden_rows: usize,
+    /// How figures spawn, for the shapes that don't spawn the default way.
+    pub spawn: SpawnRules,
+    /// Number of next pieces shown.
+    pub preview: usize,
+    /// Whether players can hold their `Figure` to use it later.
+    pub hold: bool,
+    /// Initial Hold System: holding the hold key while a `Figure` spawns holds it right away.
+    pub ihs: bool,
+    /// Initial Rotation System: holding a rotation key while a `Figure` spawns rotates it right away.
+    pub irs: bool,
+    pub game_over: GameOverRules,
+    /// Which spins are recognized when figures lock.
+    pub spin_rule: SpinRule,
+    /// Lines of garbage sent to the opponents when clearing rows.
+    pub attack: AttackTable,
+    /// Ticks garbage waits before it can enter the grid, during which it can be cancelled by clearing rows.
+    pub garbage_delay: u32,
+    /// Maximum lines of garbage entering the grid each time a `Figure` locks without clearing rows.
+    pub garbage_cap: u32,
+    /// Where the holes of the garbage entering the grid are.
+    pub garbage_style: GarbageStyle,
+}
+
+impl Default for GridConfig {
+    fn default() -> Self {
+        Self {
+            tps: 30,
+            physics: PhysicsConfig::default(),
+            hidden_rows: 20,
+            spawn: SpawnRules::new(),
+            preview: 5,
+            hold: true,
+            ihs: false,
+            irs: false,
+            game_over: GameOverRules::default(),
+            spin_rule: SpinRule::default(),
+            attack: AttackTable::default(),
+            garbage_delay: 10,
+            garbage_cap: 8,
+            garbage_style: GarbageStyle::default(),
+        }
+    }
+}
+
+/// Which rules end the game of a grid. A disabled rule lets the game go on: the `Figure` spawns or locks anyway, and
+/// the blocks pushed past the top are lost.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameOverRules {
+    /// A `Figure` spawning overlapping the blocks of the grid ends the game.
+    pub block_out: bool,
+    /// A `Figure` locking fully above the visible area of the grid ends the game.
+    pub lock_out: bool,
+    /// Garbage pushing blocks past the top of the grid ends the game.
+    pub top_out: bool,
+}
+
+impl Default for GameOverRules {
+    fn default() -> Self {
+        Self {
+            block_out: true,
+            lock_out: true,
+            top_out: true,
+        }
+    }
+}
 
+/// Seeds of the random number generators of a `GridSession`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridSeeds {
+    /// Seed of the pieces that spawn in the grid.
+    pub pieces: u64,
+    /// Seed of any other random event of the grid.
+    pub grid: u64,
+}
+
+/// Runs a `GridEngine` in real time, in its own thread: the messages received from the `AtrisSession` are given to the
+/// engine on its next tick, and the events it produces are sent back. The ticks follow a `Ticker`, on the wall clock
+/// unless another `Clock` is set.
 #[derive(Debug)]
 pub struct GridSession<'a> {
-    id: usize,
-    grid: Grid<'a>,
-    // blocks: Vec<PositionedBlock<'a>>,
-    figures: HashMap<usize, Figure<'a>>,
-    // Configurations:
-    tps: u64,
-    // State:
-    state: State<u64>,
-    rng: ThreadRng, //Arc/Rc<Mutex?<StdRng>>,
-    loop_timestamp: Instant,
+    engine: GridEngine<'a>,
+    ticker: Ticker,
+    /// Messages received for the next tick.
+    messages: Vec<SessionEvent>,
+    /// Recording of the messages given to the engine on every tick, if recording.
+    recording: Option<GridReplay>,
     // Events channels:
-    sender: Sender<()>,
-    receiver: Receiver<()>
+    sender: Sender<GridEvent>,
+    receiver: Receiver<SessionEvent>
 }
 
 impl<'a> GridSession<'a> {
-    pub fn new(grid_session_id: usize, grid_bounds: UVector, sender: Sender<()>, receiver: Receiver<()>, tps: u64) -> Self {
+    pub fn new(grid_session_id: usize, grid_bounds: UVector, game_mode: &'a dyn GameMode<StdRng>, config: GridConfig,
+            seeds: GridSeeds, sender: Sender<GridEvent>, receiver: Receiver<SessionEvent>) -> Self {
+        let ticker = Ticker::new(Box::new(RealClock::new()), config.tps);
         Self {
-            id: grid_session_id,
-            grid: Grid::new(grid_bounds),
-            figures: HashMap::new(),
-            tps: tps,
-            state: State::new(),
-            rng: thread_rng(),
-            loop_timestamp: Instant::now(),
-            sender: sender,
-            receiver: receiver,
+            engine: GridEngine::new(grid_session_id, grid_bounds, game_mode, config, seeds),
+            ticker,
+            messages: Vec::new(),
+            recording: None,
+            sender,
+            receiver,
         }
     }
 
+    pub fn id(&self) -> usize { self.engine.id() }
+
+    /// Returns the engine running the grid.
+    pub fn engine(&self) -> &GridEngine<'a> { &self.engine }
+
+    pub fn engine_mut(&mut self) -> &mut GridEngine<'a> { &mut self.engine }
+
+    /// Returns the ticker deciding when the ticks of the grid run.
+    pub fn ticker(&self) -> &Ticker { &self.ticker }
+
+    pub fn ticker_mut(&mut self) -> &mut Ticker { &mut self.ticker }
+
+    /// Replaces the clock the ticks follow, at normal speed.
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.ticker = Ticker::new(clock, self.engine.config().tps);
+    }
+
+    /// Checks whether the game loop is recorded.
+    pub fn is_recording(&self) -> bool { self.recording.is_some() }
+
+    /// Sets whether the game loop is recorded. The recording is sent with a `GridEvent::Replay` message when the loop
+    /// stops. It must be set before the game starts to be played again.
+    pub fn set_recording(&mut self, recording: bool) {
+        self.recording = recording.then(|| {
+            let grid = self.engine.grid();
+            let grid_bounds = Vector(grid.n_cols(), grid.visible_rows());
+            let config = self.engine.config().clone();
+            GridReplay::new(self.engine.id(), grid_bounds, self.engine.game_mode(), config, self.engine.seeds())
+        });
+    }
+
+    /// Waits for the `SessionEvent::Start` message and starts the game loop, handling any other message received
+    /// before it. Returns without starting if the session stops or is dropped first.
     pub fn await_until_start(&mut self) {
-        // Check that didn't already started
-        match self.receiver.recv() {
-            Ok(e) => self.start(), // GameStart or GridAdded(Receiver)
-            Err(_) => (),
+        while let Ok(e) = self.receiver.recv() {
+            match e {
+                SessionEvent::Start => return self.start(),
+                SessionEvent::Stop => return,
+                e => self.handle_message(e),
+            }
         };
     }
 
+    /// Handles the messages received since the last call. If the session was dropped, the grid is stopped.
     fn get_messages(&mut self) {
-        for e in self.receiver.try_iter() {
-            unimplemented!();
-            // One of the message types may be GridAdded(Receiver)
+        loop {
+            match self.receiver.try_recv() {
+                Ok(e) => self.handle_message(e),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.handle_message(SessionEvent::Stop);
+                    break;
+                },
+            }
         };
     }
 
-    pub fn start(&mut self) {
-        let between_ticks = Duration::from_millis(1000 / self.tps);
-        loop {
-            self.loop_timestamp = Instant::now();
-            self.game_loop();
-            let timestamp = Instant::now();
-            let diff = timestamp - self.loop_timestamp;
-            if between_ticks > diff {
-                sleep(between_ticks - diff);
-            }
+    /// Applies the messages about timing to the ticker, and keeps the others for the next tick. Stopping is applied
+    /// right away, so that it works even while frame stepping.
+    fn handle_message(&mut self, e: SessionEvent) {
+        match e {
+            SessionEvent::Speed(speed) => self.ticker.set_speed(speed),
+            SessionEvent::FrameStepping(frame_stepping) => self.ticker.set_frame_stepping(frame_stepping),
+            SessionEvent::StepFrame => self.ticker.step_frame(),
+            SessionEvent::Stop => self.engine.handle(SessionEvent::Stop),
+            e => self.messages.push(e),
         }
     }
 
-    fn game_loop(&mut self) {
-        self.get_messages();
-        //...
+    /// Runs the game loop until the session stops it or the game is over. Late ticks are caught up. When recording, the
+    /// recording is sent once the loop stops.
+    pub fn start(&mut self) {
+        self.engine.start();
+        self.ticker.restart_schedule();
+        while self.engine.is_running() {
+            self.get_messages();
+            for _ in 0..self.ticker.due() {
+                if !self.engine.is_running() {
+                    break;
+                }
+                let messages = std::mem::take(&mut self.messages);
+                if let Some(recording) = self.recording.as_mut() {
+                    recording.record(&messages);
+                }
+                for e in self.engine.step(messages) {
+                    // Messages are lost if the session was dropped
+                    let _ = self.sender.send(e);
+                }
+            }
+            self.ticker.wait();
+        }
+        if let Some(mut recording) = self.recording.take() {
+            recording.finish(&self.engine);
+            let _ = self.sender.send(GridEvent::Replay(Box::new(recording)));
+        }
     }
-}
\ No newline at end of file
+}
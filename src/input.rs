@@ -0,0 +1,128 @@
+/// Actions a player can do with their `Figure`, each bound to a key.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Action {
+    Left,
+    Right,
+    SoftDrop,
+    HardDrop,
+    RotateCW,
+    RotateCCW,
+    Rotate180,
+    Hold,
+}
+
+/// A raw key event of a player, as sent by a frontend.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlayerInput {
+    Press(Action),
+    Release(Action),
+}
+
+/// How the inputs of a player are handled. Delays are in ticks.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HandlingConfig {
+    /// Delayed Auto Shift: ticks a horizontal key must be held before the `Figure` starts shifting by itself.
+    pub das: u32,
+    /// Auto Repeat Rate: ticks between two automatic shifts. With 0, the `Figure` shifts to the wall at once.
+    pub arr: u32,
+    /// Factor gravity is multiplied by while soft dropping.
+    pub soft_drop_factor: u32,
+}
+
+impl Default for HandlingConfig {
+    fn default() -> Self {
+        Self {
+            das: 5,
+            arr: 1,
+            soft_drop_factor: 20,
+        }
+    }
+}
+
+/// State of the horizontal keys of a player, that makes the `Figure` shift automatically while they are held. The last
+/// key pressed wins, and releasing it gives back the control to the other one if it's still held.
+///
+/// ```
+/// use atris::input::{AutoShift, HandlingConfig};
+///
+/// let config = HandlingConfig { das: 3, arr: 2, ..Default::default() };
+/// let mut shift = AutoShift::new();
+/// assert!(shift.press(-1)); // Shifts once right away
+/// assert!(!shift.press(-1)); // Already held
+/// let shifts: Vec<u32> = (0..7).map(|_| shift.tick(&config)).collect();
+/// assert_eq!(shifts, vec!(0, 0, 1, 0, 1, 0, 1));
+/// shift.press(1);
+/// assert_eq!(shift.direction(), 1);
+/// shift.release(1); // Back to the left key, charging the DAS again
+/// assert_eq!(shift.direction(), -1);
+/// assert_eq!(shift.tick(&config), 0);
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AutoShift {
+    left: bool,
+    right: bool,
+    direction: i32,
+    timer: u32,
+}
+
+impl AutoShift {
+    pub fn new() -> Self {
+        Self {
+            left: false,
+            right: false,
+            direction: 0,
+            timer: 0,
+        }
+    }
+
+    /// Returns the direction the `Figure` shifts to: -1 (left), 1 (right) or 0 (no horizontal key held).
+    pub fn direction(&self) -> i32 { self.direction }
+
+    /// Presses the key of a direction (-1 or 1). Returns whether the `Figure` must shift once right away, which is the
+    /// case unless the key was already held.
+    pub fn press(&mut self, direction: i32) -> bool {
+        let held = if direction < 0 { &mut self.left } else { &mut self.right };
+        if *held {
+            return false;
+        }
+        *held = true;
+        self.direction = direction.signum();
+        self.timer = 0;
+        true
+    }
+
+    /// Releases the key of a direction (-1 or 1).
+    pub fn release(&mut self, direction: i32) {
+        if direction < 0 {
+            self.left = false;
+        } else {
+            self.right = false;
+        }
+        if self.direction == direction.signum() {
+            self.direction = if self.left { -1 } else if self.right { 1 } else { 0 };
+            self.timer = 0;
+        }
+    }
+
+    /// Advances one tick, and returns how many cells the `Figure` must shift in this tick. Returns `u32::MAX` when it
+    /// must shift as far as it can.
+    pub fn tick(&mut self, config: &HandlingConfig) -> u32 {
+        if self.direction == 0 {
+            return 0;
+        }
+        self.timer = self.timer.saturating_add(1);
+        if self.timer < config.das {
+            0
+        } else if config.arr == 0 {
+            u32::MAX
+        } else if (self.timer - config.das) % config.arr == 0 {
+            1
+        } else {
+            0
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use crate::physics::{PhysicsConfig, CELL};
+
+/// How the level of a grid advances.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LevelProgression {
+    /// One level every given number of lines cleared.
+    Lines(u64),
+    /// One level every given number of frames (1/60 s) played.
+    Time(u64),
+}
+
+/// The speed of a level. Times are in frames (1/60 s), and gravity in 1/`CELL` cells every `gravity_frames` frames, so
+/// they don't depend on the ticks per second of the grids.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Speed {
+    pub gravity: u32,
+    pub gravity_frames: u32,
+    pub lock_delay: u32,
+    pub are: u32,
+    pub clear_delay: u32,
+}
+
+impl Speed {
+    /// Sets the gravity and delays of `physics`, for a grid running at `tps` ticks per second, to this speed. The
+    /// gravity is kept exact.
+    ///
+    /// ```
+    /// use atris::level::Speed;
+    /// use atris::physics::{PhysicsConfig, CELL};
+    ///
+    /// let mut physics = PhysicsConfig::default();
+    /// Speed { gravity: CELL / 4, gravity_frames: 1, lock_delay: 30, are: 7, clear_delay: 0 }.apply(&mut physics, 30);
+    /// assert_eq!((physics.gravity, physics.lock_delay, physics.are, physics.clear_delay), (CELL / 2, 15, 4, 0));
+    /// Speed { gravity: CELL, gravity_frames: 43, lock_delay: 0, are: 0, clear_delay: 0 }.apply(&mut physics, 30);
+    /// assert_eq!((physics.gravity, physics.gravity_ticks), (2 * CELL, 43)); // 21.5 ticks per row
+    /// ```
+    pub fn apply(&self, physics: &mut PhysicsConfig, tps: u64) {
+        let tps = tps.max(1);
+        let ticks = |frames: u32| ((frames as u64 * tps + 30) / 60).min(u32::MAX as u64) as u32;
+        let distance = self.gravity as u64 * 60;
+        let period = self.gravity_frames.max(1) as u64 * tps;
+        let divisor = gcd(distance, period);
+        physics.gravity = (distance / divisor).min(u32::MAX as u64) as u32;
+        physics.gravity_ticks = (period / divisor).min(u32::MAX as u64) as u32;
+        physics.lock_delay = ticks(self.lock_delay);
+        physics.are = ticks(self.are);
+        physics.clear_delay = ticks(self.clear_delay);
+    }
+}
+
+/// A curve table giving the `Speed` of every level, and the way levels advance.
+///
+/// ```
+/// use atris::level::{LevelCurve, LevelProgression};
+/// use atris::physics::CELL;
+///
+/// let nes = LevelCurve::nes();
+/// assert_eq!(nes.level(25, 0), 2);
+/// assert_eq!((nes.speed(0).gravity, nes.speed(0).gravity_frames), (CELL, 48));
+/// assert_eq!((nes.speed(29).gravity, nes.speed(29).gravity_frames), (CELL, 1));
+/// let tgm = LevelCurve::tgm_master();
+/// assert_eq!(tgm.progression(), LevelProgression::Lines(1));
+/// assert_eq!(tgm.speed(35).gravity, 8);
+/// assert_eq!(tgm.speed(999).gravity, 20 * CELL);
+/// assert_eq!(tgm.level(5000, 0), 999);
+/// let guideline = LevelCurve::guideline();
+/// assert!(guideline.speed(2).gravity > guideline.speed(1).gravity);
+/// assert_eq!(guideline.speed(20).gravity, 20 * CELL);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LevelCurve {
+    progression: LevelProgression,
+    start_level: u32,
+    max_level: u32,
+    /// The levels where the speed changes, in ascending order, with their new speed.
+    speeds: Vec<(u32, Speed)>,
+}
+
+impl LevelCurve {
+    /// Creates a curve where levels go from `start_level` to `max_level` following `progression`, and each level gets
+    /// the speed of the last entry of `speeds` starting at it or before it.
+    pub fn new(progression: LevelProgression, start_level: u32, max_level: u32, mut speeds: Vec<(u32, Speed)>)
+            -> Self {
+        speeds.sort_by_key(|&(level, _)| level);
+        Self {
+            progression,
+            start_level,
+            max_level: max_level.max(start_level),
+            speeds,
+        }
+    }
+
+    /// Curve of the Tetris guideline: 20 levels, starting at 1, one every 10 lines, with the gravity of each level
+    /// taking `(0.8 - (level - 1) * 0.007)^(level - 1)` seconds per row, up to 20G.
+    pub fn guideline() -> Self {
+        let speeds = (1..=20).map(|level| {
+            let seconds_per_row = (0.8 - (level - 1) as f64 * 0.007).powi(level as i32 - 1);
+            let gravity = (CELL as f64 / (60.0 * seconds_per_row)).round() as u32;
+            let gravity = gravity.min(20 * CELL);
+            (level, Speed { gravity, gravity_frames: 1, lock_delay: 30, are: 6, clear_delay: 12 })
+        }).collect();
+        Self::new(LevelProgression::Lines(10), 1, 20, speeds)
+    }
+
+    /// Curve of NES Tetris: levels start at 0 and advance every 10 lines, figures fall one row every 48 frames at level
+    /// 0 and every frame from level 29, and lock as soon as they land.
+    pub fn nes() -> Self {
+        let frames_per_row = [
+            (0, 48), (1, 43), (2, 38), (3, 33), (4, 28), (5, 23), (6, 18), (7, 13), (8, 8), (9, 6),
+            (10, 5), (13, 4), (16, 3), (19, 2), (29, 1),
+        ];
+        let speeds = frames_per_row.into_iter().map(|(level, frames)| {
+            (level, Speed { gravity: CELL, gravity_frames: frames, lock_delay: 0, are: 10, clear_delay: 18 })
+        }).collect();
+        Self::new(LevelProgression::Lines(10), 0, u32::MAX, speeds)
+    }
+
+    /// Curve of the master mode of TGM2: levels go from 0 to 999, one per line cleared (pieces don't make it advance
+    /// here), gravity follows the TGM table up to 20G at level 500, and the delays shorten from level 500.
+    pub fn tgm_master() -> Self {
+        let gravities = [
+            (0, 4), (30, 6), (35, 8), (40, 10), (50, 12), (60, 16), (70, 32), (80, 48), (90, 64), (100, 80),
+            (120, 96), (140, 112), (160, 128), (170, 144), (200, 4), (220, 32), (230, 64), (233, 96), (236, 128),
+            (239, 160), (243, 192), (247, 224), (251, 256), (300, 512), (330, 768), (360, 1024), (400, 1280),
+            (420, 1024), (450, 768), (500, 5120),
+        ];
+        // Level, ARE, lock delay and clear delay
+        let delays = [(0, 25, 30, 40), (500, 25, 30, 25), (600, 25, 30, 16), (700, 16, 30, 12), (800, 12, 30, 6),
+            (900, 12, 17, 6)];
+        let mut levels: Vec<u32> = gravities.iter().map(|g| g.0).chain(delays.iter().map(|d| d.0)).collect();
+        levels.sort_unstable();
+        levels.dedup();
+        let speeds = levels.into_iter().map(|level| {
+            let gravity = gravities.iter().rev().find(|g| g.0 <= level).unwrap().1;
+            let &(_, are, lock_delay, clear_delay) = delays.iter().rev().find(|d| d.0 <= level).unwrap();
+            (level, Speed { gravity, gravity_frames: 1, lock_delay, are, clear_delay })
+        }).collect();
+        Self::new(LevelProgression::Lines(1), 0, 999, speeds)
+    }
+
+    pub fn progression(&self) -> LevelProgression { self.progression }
+
+    pub fn start_level(&self) -> u32 { self.start_level }
+
+    pub fn max_level(&self) -> u32 { self.max_level }
+
+    /// Returns the levels where the speed changes, in ascending order, with their new speed.
+    pub fn speeds(&self) -> &[(u32, Speed)] { &self.speeds }
+
+    /// Returns the speed of `level`.
+    pub fn speed(&self, level: u32) -> Speed {
+        self.speeds.iter().rev().find(|&&(from, _)| from <= level).or(self.speeds.first())
+            .map(|&(_, speed)| speed)
+            .unwrap_or(Speed { gravity: CELL, gravity_frames: 60, lock_delay: 30, are: 0, clear_delay: 0 })
+    }
+
+    /// Returns the level reached after clearing `lines` lines and playing `frames` frames.
+    pub fn level(&self, lines: u64, frames: u64) -> u32 {
+        let levels = match self.progression {
+            LevelProgression::Lines(lines_per_level) => lines / lines_per_level.max(1),
+            LevelProgression::Time(frames_per_level) => frames / frames_per_level.max(1),
+        };
+        (self.start_level as u64 + levels).min(self.max_level as u64) as u32
+    }
+}
+
+/// Returns the greatest common divisor of `a` and `b`, or 1 if both are 0.
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.max(1)
+}
@@ -0,0 +1,166 @@
+use crate::algebra::Vector;
+use crate::grid::{Figure, Grid};
+
+/// Subdivisions of a cell in which gravity is measured: a gravity of `CELL` makes figures fall one cell per tick, and a
+/// gravity of `20 * CELL` (20G) makes them fall to the bottom in the same tick they spawn.
+pub const CELL: u32 = 256;
+
+/// When the lock delay of a grounded `Figure` starts over.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LockReset {
+    /// Every successful move or rotation resets the lock delay, so a `Figure` can be kept from locking forever.
+    Infinity,
+    /// Every successful move or rotation resets the lock delay, up to the given number of times. The count starts over
+    /// when the `Figure` falls below the lowest row it reached before.
+    MoveReset(u32),
+    /// Only falling below the lowest row the `Figure` reached before resets the lock delay.
+    StepReset,
+}
+
+/// Configuration of how figures fall and lock.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicsConfig {
+    /// Distance figures fall every `gravity_ticks` ticks, in 1/`CELL` cells.
+    pub gravity: u32,
+    /// Ticks over which figures fall `gravity`, so that slow gravities can be exact.
+    pub gravity_ticks: u32,
+    /// Ticks a `Figure` can stay on the ground before locking.
+    pub lock_delay: u32,
+    pub lock_reset: LockReset,
+    /// Ticks between a `Figure` locking and the next one spawning (ARE).
+    pub are: u32,
+    /// Ticks full rows stay in the `Grid` before being cleared. When a `Figure` clears rows, the next one spawns after
+    /// both this delay and the ARE.
+    pub clear_delay: u32,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            gravity: CELL / 32,
+            gravity_ticks: 1,
+            lock_delay: 15,
+            lock_reset: LockReset::MoveReset(15),
+            are: 2,
+            clear_delay: 6,
+        }
+    }
+}
+
+/// Physics state of a falling `Figure`: how much it has fallen since the last whole cell, and how long it has been on
+/// the ground. It must be reset (replaced with a new one) every time a new `Figure` spawns.
+///
+/// ```
+/// use atris::algebra::Vector;
+/// use atris::grid::{Figure, Grid};
+/// use atris::piece::{shape::Tetromino, Piece};
+/// use atris::blocktypes::standard::StandardType;
+/// use atris::physics::{Physics, PhysicsConfig, LockReset, CELL};
+///
+/// let bt = StandardType{};
+/// let grid = Grid::new(Vector(10, 20));
+/// let mut fig = Figure::new(Piece::uniform(&bt, Tetromino::O.shape()), Vector(4, 3));
+/// let config = PhysicsConfig { gravity: CELL / 2, lock_delay: 2, lock_reset: LockReset::StepReset, ..Default::default() };
+/// let mut physics = Physics::new();
+/// assert!(!physics.step(&config, &grid, &mut fig));
+/// assert_eq!(fig.position, Vector(4, 3));
+/// assert!(!physics.step(&config, &grid, &mut fig));
+/// assert_eq!(fig.position, Vector(4, 2));
+/// physics.set_soft_drop(Some(20));
+/// assert!(!physics.step(&config, &grid, &mut fig)); // Falls to the ground
+/// assert_eq!(fig.position, Vector(4, 0));
+/// assert!(!physics.step(&config, &grid, &mut fig)); // Stays on the ground
+/// assert!(physics.step(&config, &grid, &mut fig)); // Locks once the lock delay is over
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Physics {
+    fall: u32,
+    lock_timer: u32,
+    resets: u32,
+    lowest_row: Option<i32>,
+    soft_drop: Option<u32>,
+}
+
+impl Physics {
+    /// Creates a new `Physics` object for a `Figure` that just spawned.
+    pub fn new() -> Self {
+        Self {
+            fall: 0,
+            lock_timer: 0,
+            resets: 0,
+            lowest_row: None,
+            soft_drop: None,
+        }
+    }
+
+    /// Starts soft dropping, multiplying gravity by the given factor, or stops it with `None`. Soft dropping makes
+    /// figures fall at least one cell per tick.
+    pub fn set_soft_drop(&mut self, soft_drop_factor: Option<u32>) {
+        self.soft_drop = soft_drop_factor;
+    }
+
+    /// Returns the factor gravity is multiplied by, if the `Figure` is being soft dropped.
+    pub fn soft_drop(&self) -> Option<u32> { self.soft_drop }
+
+    /// Returns for how many ticks the `Figure` has been on the ground since the lock delay was last reset.
+    pub fn lock_timer(&self) -> u32 { self.lock_timer }
+
+    /// Advances one tick: makes `fig` fall according to the gravity, and returns whether it must lock now.
+    pub fn step<'bt>(&mut self, config: &PhysicsConfig, grid: &Grid<'bt>, fig: &mut Figure<'bt>) -> bool {
+        self.update_lowest_row(fig);
+        // The fall is counted in 1/`gravity_ticks` of the gravity units
+        let cell = CELL.saturating_mul(config.gravity_ticks.max(1));
+        let gravity = match self.soft_drop {
+            Some(factor) => config.gravity.saturating_mul(factor).max(cell),
+            None => config.gravity,
+        };
+        self.fall = self.fall.saturating_add(gravity);
+        while self.fall >= cell {
+            if !grid.try_move(fig, Vector(0, -1)).success {
+                break;
+            }
+            self.fall -= cell;
+            self.update_lowest_row(fig);
+        }
+        if grid.fits_in(fig, Vector(0, -1), 0) {
+            return false;
+        }
+        // Figures don't keep falling momentum while on the ground
+        self.fall = 0;
+        self.lock_timer += 1;
+        self.lock_timer > config.lock_delay
+    }
+
+    /// Drops `fig` to the ground, and returns how many rows it fell. It must lock right after.
+    pub fn hard_drop<'bt>(&mut self, grid: &Grid<'bt>, fig: &mut Figure<'bt>) -> i32 {
+        let distance = grid.drop_distance(fig);
+        self.fall = 0;
+        fig.position = fig.position + Vector(0, -distance);
+        distance
+    }
+
+    /// Notifies that `fig` was successfully moved or rotated, so the lock delay may be reset.
+    pub fn on_moved(&mut self, config: &PhysicsConfig, fig: &Figure) {
+        self.update_lowest_row(fig);
+        match config.lock_reset {
+            LockReset::Infinity => self.lock_timer = 0,
+            LockReset::MoveReset(max_resets) => if self.resets < max_resets {
+                self.resets += 1;
+                self.lock_timer = 0;
+            },
+            LockReset::StepReset => (),
+        }
+    }
+
+    /// Resets the lock delay (and the move reset count) if `fig` is lower than ever before.
+    fn update_lowest_row(&mut self, fig: &Figure) {
+        if self.lowest_row.map_or(true, |row| fig.position.1 < row) {
+            self.lowest_row = Some(fig.position.1);
+            self.lock_timer = 0;
+            self.resets = 0;
+        }
+    }
+}
@@ -1,4 +1,6 @@
 mod piece;
 pub mod shape;
 
-pub use piece::Piece;
\ No newline at end of file
+pub use piece::Piece;
+#[cfg(feature = "serde")]
+pub(crate) use piece::PieceData;
\ No newline at end of file
@@ -3,8 +3,11 @@ use std::iter::{zip, Zip};
 use crate::block::{Block, BlockType};
 use crate::algebra::IVector;
 use super::shape::Shape;
+#[cfg(feature = "serde")]
+use crate::{block::BlockData, blocktypes::BlockTypes, snapshot::{deserialize_resolved, DeserializeWith, Unresolved}};
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Piece<'bt> {
     blocks: Vec<Block<'bt>>,
     shape: Shape,
@@ -31,8 +34,8 @@ impl<'bt> Piece<'bt> {
             panic!("Creating piece with different number of blocks and points in shape");
         };
         Self {
-            blocks: blocks,
-            shape: shape,
+            blocks,
+            shape,
         }
     }
 
@@ -55,8 +58,8 @@ impl<'bt> Piece<'bt> {
         let mut blocks = Vec::new();
         blocks.resize(shape.positions().len(), Block::new(t));
         Self {
-            blocks: blocks,
-            shape: shape,
+            blocks,
+            shape,
         }
     }
 
@@ -76,3 +79,31 @@ impl<'bt> IntoIterator for Piece<'bt> {
     /// Returns an iterator over tuples made by `Block` and point (in `Shape`).
     fn into_iter(self) -> Self::IntoIter {zip(self.blocks, self.shape)}
 }
+
+/// Serialized form of a `Piece`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+pub(crate) struct PieceData {
+    blocks: Vec<BlockData>,
+    shape: Shape,
+}
+
+#[cfg(feature = "serde")]
+impl<'bt> Unresolved<'bt> for PieceData {
+    type Resolved = Piece<'bt>;
+
+    fn resolve(self, block_types: &'bt BlockTypes) -> Result<Piece<'bt>, String> {
+        if self.blocks.len() != self.shape.len() {
+            return Err("piece with different number of blocks and points in shape".to_string());
+        }
+        Ok(Piece { blocks: self.blocks.resolve(block_types)?, shape: self.shape })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'bt> DeserializeWith<'bt> for Piece<'bt> {
+    fn deserialize_with<'de, D: serde::Deserializer<'de>>(deserializer: D, block_types: &'bt BlockTypes)
+            -> Result<Self, D::Error> {
+        deserialize_resolved::<PieceData, D>(deserializer, block_types)
+    }
+}
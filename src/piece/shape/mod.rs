@@ -1,5 +1,7 @@
 pub mod shapes;
 pub mod shape;
+pub mod tetromino;
 
 pub use shape::Shape;
-pub use shapes::Shapes;
\ No newline at end of file
+pub use shapes::Shapes;
+pub use tetromino::Tetromino;
\ No newline at end of file
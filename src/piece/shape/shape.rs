@@ -1,11 +1,12 @@
 use std::iter::zip;
 use std::ops::{Add, Sub, Neg};
 
-use crate::algebra::{Vector, IVector};
+use crate::algebra::{Vector, IVector, FVector};
 
 /// Represents a shape os blocks. For example, a `Figure` must have one, so that its blocks are relatively positioned
 /// some specific way.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Shape {
     positions: Vec<IVector>,
 }
@@ -45,6 +46,29 @@ impl Shape {
         s
     }
 
+    /// Returns the shape rotated `angle`*90 degrees counter-clockwise around `center`. The coordinates of `center` are
+    /// rounded to the nearest half, so the rotated points are whole only if `center` is at the center of a cell or at a
+    /// corner between cells.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::piece::shape::Shape;
+    ///
+    /// let i = Shape::from_iter([Vector(-1, 0), Vector(0, 0), Vector(1, 0), Vector(2, 0)]);
+    /// assert_eq!(i.rotated_around(0, Vector(0.5, -0.5)).positions(), i.positions());
+    /// assert_eq!(i.rotated_around(1, Vector(0.5, -0.5)).positions(), &vec!(Vector(0, -2), Vector(0, -1), Vector(0, 0), Vector(0, 1)));
+    /// assert_eq!(i.rotated_around(2, Vector(0.5, -0.5)).positions(), &vec!(Vector(-1, -1), Vector(0, -1), Vector(1, -1), Vector(2, -1)));
+    /// assert_eq!(i.rotated_around(1, Vector(0.0, 0.0)).positions(), i.rotated(1).positions());
+    /// ```
+    pub fn rotated_around(&self, angle: i32, center: FVector) -> Self {
+        let center2 = Vector((center.0 * 2.0).round() as i32, (center.1 * 2.0).round() as i32);
+        let mut s = Self::new();
+        for &p in self.positions.iter() {
+            s.add_pos(((p * 2 - center2).rotated(angle) + center2) / 2);
+        }
+        s
+    }
+
     /// Returns the shape centered (with the origin (0,0) more or less at the center of the shape).
     pub fn centered(&self) -> Self {
         let center = self.center();
@@ -64,12 +88,13 @@ impl Shape {
     /// Returns the exact center point of the `Shape` object.
     pub fn f64_center(&self) -> Vector<f64> {
         let bounds = self.bounds();
-        <Vector<f64>>::from(bounds.0 + (bounds.1 - bounds.0)) / 2.0
+        <Vector<f64>>::from(bounds.0 + bounds.1) / 2.0
     }
 
     /// Returns the bounds of the `Shape` object as a two-`Vector<i32>` tuple: the minimum coordinates and the maximum coordinates.
     pub fn bounds(&self) -> (IVector, IVector) {
-        let mut bounds = (Vector(0,0), Vector(0,0));
+        let first = self.positions.first().copied().unwrap_or_default();
+        let mut bounds = (first, first);
         for p in self.positions.iter() {
             if p.0 < bounds.0.0 {bounds.0.0 = p.0}
             if p.1 < bounds.0.1 {bounds.0.1 = p.1}
@@ -79,9 +104,41 @@ impl Shape {
         bounds
     }
 
+    /// Returns the size of the bounds of the `Shape` object (width, height).
+    pub fn extents(&self) -> IVector {
+        let bounds = self.bounds();
+        bounds.1 - bounds.0 + Vector(1, 1)
+    }
+
+    /// Returns the shape moved so that its minimum coordinates are (0, 0). Two shapes are equal translation-wise (but
+    /// not rotation-wise) when their normalized shapes have the same points.
+    pub fn normalized(&self) -> Self {
+        self.clone() - self.bounds().0
+    }
+
+    /// Returns the number of different orientations the `Shape` has when rotated: 1, 2 or 4.
+    ///
+    /// ```
+    /// use atris::piece::shape::Tetromino;
+    ///
+    /// assert_eq!(Tetromino::O.shape().n_orientations(), 1);
+    /// assert_eq!(Tetromino::I.shape().n_orientations(), 2);
+    /// assert_eq!(Tetromino::S.shape().n_orientations(), 2);
+    /// assert_eq!(Tetromino::T.shape().n_orientations(), 4);
+    /// ```
+    pub fn n_orientations(&self) -> usize {
+        let normalized = self.normalized();
+        if self.rotated(1).normalized().positions == normalized.positions {1}
+        else if self.rotated(2).normalized().positions == normalized.positions {2}
+        else {4}
+    }
+
     /// Returns the number of points in the `Shape`.
     pub fn len(&self) -> usize { self.positions.len() }
 
+    /// Checks whether the `Shape` has no points.
+    pub fn is_empty(&self) -> bool { self.positions.is_empty() }
+
     /// Returns an inmutable reference to the inner `Vec` of points.
     pub fn positions(&self) -> &Vec<IVector> { &self.positions }
 
@@ -92,6 +149,10 @@ impl Shape {
     pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, IVector> { self.positions.iter_mut() }
 }
 
+impl Default for Shape {
+    fn default() -> Self { Self::new() }
+}
+
 impl Add<IVector> for Shape {
 	type Output = Self;
 	/// Sums a `Vector<i32>` to a `Shape`.
@@ -154,16 +215,15 @@ impl PartialEq for Shape {
         if self.positions.len() != rhs.positions.len() {
             return false;
         }
-        if self.positions.len() == 0 {
+        if self.positions.is_empty() {
             return true;
         }
         'angle: for angle in 0..4 {
-            let rotated_rhs;
-            if angle == 0 {
-                rotated_rhs = rhs.clone();
+            let rotated_rhs = if angle == 0 {
+                rhs.clone()
             } else {
-                rotated_rhs = rhs.rotated(angle);
-            }
+                rhs.rotated(angle)
+            };
             let d = self.positions[0] - rotated_rhs.positions[0];
             for (&pself, &prhs) in zip(self.positions.iter(), rotated_rhs.positions.iter()) {
                 if pself != prhs + d {
@@ -174,4 +234,6 @@ impl PartialEq for Shape {
         }
         false
     }
-}
\ No newline at end of file
+}
+
+impl Eq for Shape {}
\ No newline at end of file
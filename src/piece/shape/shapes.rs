@@ -12,6 +12,10 @@ pub struct Shapes {
     // rng: &'r mut ThreadRng,
 }
 
+impl Default for Shapes {
+    fn default() -> Self { Self::new() }
+}
+
 impl Shapes {
     /// Creates a list with the list of all possible shapes of `size` 1 (just one shape with just one point: origin).
     /// `Self::size` is first set to 1.
@@ -131,7 +135,7 @@ impl Shapes {
         let mut shapes = Vec::new();
         for last_shape in self.last_shapes().iter() {
             for p in last_shape.clone() {
-                for dir in vec!(Vector(1,0), Vector(0,1), Vector(-1,0), Vector(0,-1)) {
+                for dir in [Vector(1,0), Vector(0,1), Vector(-1,0), Vector(0,-1)] {
                     let p2 = p + dir;
                     if !last_shape.contains(&p2) {
                         let mut shape = last_shape.clone();
@@ -0,0 +1,51 @@
+use crate::algebra::Vector;
+use super::Shape;
+
+/// The seven one-sided tetrominoes. They are useful to find out which piece a 4-block `Shape` is, independently of
+/// its rotation and position, so rotation systems and scoring rules can treat them specially.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Tetromino {
+    I, O, T, S, Z, J, L,
+}
+
+impl Tetromino {
+    /// All the tetrominoes, in the usual order.
+    pub const ALL: [Tetromino; 7] = [Self::I, Self::O, Self::T, Self::S, Self::Z, Self::J, Self::L];
+
+    /// Returns which tetromino `shape` is, if any.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::piece::shape::{Shape, Shapes, Tetromino};
+    ///
+    /// let t = Shape::from_iter([Vector(0, -1), Vector(0, 0), Vector(0, 1), Vector(1, 0)]);
+    /// assert_eq!(Tetromino::of(&t), Some(Tetromino::T));
+    /// assert_eq!(Tetromino::of(&Tetromino::S.shape().rotated(3)), Some(Tetromino::S));
+    /// assert_eq!(Tetromino::of(&Shape::unit()), None);
+    ///
+    /// let mut s = Shapes::new();
+    /// s.gen_until(4);
+    /// assert!(s.shapes(4).iter().all(|shape| Tetromino::of(shape).is_some()));
+    /// ```
+    pub fn of(shape: &Shape) -> Option<Self> {
+        if shape.len() != 4 {
+            return None;
+        }
+        Self::ALL.into_iter().find(|t| &t.shape() == shape)
+    }
+
+    /// Returns the `Shape` of the tetromino in its guideline spawn orientation (flat side down), with the origin at one
+    /// of its central blocks.
+    pub fn shape(&self) -> Shape {
+        let positions = match self {
+            Self::I => [Vector(-1, 0), Vector(0, 0), Vector(1, 0), Vector(2, 0)],
+            Self::O => [Vector(0, 0), Vector(1, 0), Vector(0, 1), Vector(1, 1)],
+            Self::T => [Vector(-1, 0), Vector(0, 0), Vector(1, 0), Vector(0, 1)],
+            Self::S => [Vector(-1, 0), Vector(0, 0), Vector(0, 1), Vector(1, 1)],
+            Self::Z => [Vector(-1, 1), Vector(0, 1), Vector(0, 0), Vector(1, 0)],
+            Self::J => [Vector(-1, 1), Vector(-1, 0), Vector(0, 0), Vector(1, 0)],
+            Self::L => [Vector(1, 1), Vector(-1, 0), Vector(0, 0), Vector(1, 0)],
+        };
+        Shape::from_iter(positions)
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::VecDeque;
+
+use rand::Rng;
+
+use crate::{gamemode::GameMode, piece::Piece, randomizer::Randomizer};
+
+/// The pieces that will spawn next in a grid, generated by a `GameMode` ahead of time so that some of them can be
+/// previewed. The queue keeps the `Randomizer` of the grid.
+///
+/// ```
+/// use atris::gamemode::GameMode;
+/// use atris::gamemodes::standard::StandardMode;
+/// use atris::piecequeue::PieceQueue;
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let mut rng = StdRng::seed_from_u64(0);
+/// let mut game_mode = StandardMode::new();
+/// GameMode::<StdRng>::initialize(&mut game_mode);
+/// let mut queue = PieceQueue::new(3, GameMode::<StdRng>::randomizer(&game_mode));
+/// queue.fill(&game_mode, &mut rng);
+/// let second = queue.previews().nth(1).unwrap().shape().clone();
+/// queue.next(&game_mode, &mut rng);
+/// assert_eq!(queue.previews().count(), 3);
+/// assert_eq!(queue.next(&game_mode, &mut rng).shape(), &second);
+/// ```
+#[derive(Debug)]
+pub struct PieceQueue<'a> {
+    pieces: VecDeque<Piece<'a>>,
+    preview: usize,
+    randomizer: Box<dyn Randomizer>,
+    /// Number of pieces generated so far.
+    generated: u64,
+}
+
+impl<'a> PieceQueue<'a> {
+    /// Creates an empty queue that shows the next `preview` pieces once filled, picked with `randomizer`.
+    pub fn new(preview: usize, randomizer: Box<dyn Randomizer>) -> Self {
+        Self {
+            pieces: VecDeque::new(),
+            preview,
+            randomizer,
+            generated: 0,
+        }
+    }
+
+    /// Returns the number of pieces that can be previewed.
+    pub fn preview(&self) -> usize { self.preview }
+
+    /// Changes the number of pieces that can be previewed. The queue shows more pieces once filled again.
+    pub fn set_preview(&mut self, preview: usize) {
+        self.preview = preview;
+    }
+
+    /// Returns the number of pieces generated so far, including the ones still in the queue.
+    pub fn generated(&self) -> u64 { self.generated }
+
+    /// Returns the pieces that will spawn next, in order.
+    pub fn previews(&self) -> impl Iterator<Item = &Piece<'a>> {
+        self.pieces.iter().take(self.preview)
+    }
+
+    /// Generates pieces until there are enough to preview.
+    pub fn fill<R: Rng>(&mut self, game_mode: &'a dyn GameMode<R>, rng: &mut R) {
+        while self.pieces.len() < self.preview {
+            let piece = self.generate(game_mode, rng);
+            self.pieces.push_back(piece);
+        }
+    }
+
+    fn generate<R: Rng>(&mut self, game_mode: &'a dyn GameMode<R>, rng: &mut R) -> Piece<'a> {
+        self.generated += 1;
+        game_mode.next_piece(self.randomizer.as_mut(), rng)
+    }
+
+    /// Generates pieces until `generated` pieces were generated in total, keeping the last `pending` ones in the queue.
+    /// Starting from a new queue and `rng` created like the ones of another queue, it ends up in the same state as
+    /// the other one after generating as many pieces: same pieces, same randomizer and same `rng`.
+    pub fn regenerate<R: Rng>(&mut self, game_mode: &'a dyn GameMode<R>, rng: &mut R, generated: u64,
+            pending: usize) {
+        while self.generated < generated {
+            let piece = self.generate(game_mode, rng);
+            self.pieces.push_back(piece);
+            if self.pieces.len() > pending {
+                self.pieces.pop_front();
+            }
+        }
+    }
+
+    /// Takes the next piece out of the queue, and fills it again.
+    pub fn next<R: Rng>(&mut self, game_mode: &'a dyn GameMode<R>, rng: &mut R) -> Piece<'a> {
+        let piece = match self.pieces.pop_front() {
+            Some(piece) => piece,
+            None => self.generate(game_mode, rng),
+        };
+        self.fill(game_mode, rng);
+        piece
+    }
+}
+
+/// A `PieceQueue` is serialized with the id of its `Randomizer` and the number of pieces generated and still in the
+/// queue, enough to generate them again with `regenerate`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for PieceQueue<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut queue = serializer.serialize_struct("PieceQueue", 4)?;
+        queue.serialize_field("preview", &self.preview)?;
+        queue.serialize_field("randomizer", self.randomizer.id())?;
+        queue.serialize_field("generated", &self.generated)?;
+        queue.serialize_field("pending", &self.pieces.len())?;
+        queue.end()
+    }
+}
@@ -0,0 +1,18 @@
+use std::fmt::Debug;
+
+use rand::RngCore;
+
+/// This `trait` represents how the next piece is picked among a set of possible ones (usually the shapes returned by
+/// `Shapes::shapes(n)`). Randomizers can keep a state (a bag, a history...), so each grid uses its own.
+pub trait Randomizer: Send + Sync + Debug {
+    /// Returns the identifier (an unique name) of the `Randomizer` object. The value returned must be constant all the
+    /// time.
+    fn id(&self) -> &'static str;
+
+    /// Picks the index of the next piece among `n` possible ones. Randomizers keeping a state expect `n` to be the same
+    /// every time, and start over when it changes.
+    fn next(&mut self, n: usize, rng: &mut dyn RngCore) -> usize;
+
+    /// Returns a new `Randomizer` with the same settings, in its initial state.
+    fn restarted(&self) -> Box<dyn Randomizer>;
+}
@@ -0,0 +1,60 @@
+use rand::{seq::SliceRandom, RngCore};
+
+use crate::randomizer::Randomizer;
+
+/// Puts a number of copies of every piece in a bag, and draws them in random order until the bag is empty. With the 7
+/// tetrominoes, one copy makes the guideline 7-bag and two copies the 14-bag, but it works with any set of pieces.
+///
+/// ```
+/// use atris::randomizer::Randomizer;
+/// use atris::randomizers::Bag;
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let mut rng = StdRng::seed_from_u64(0);
+/// let mut bag = Bag::new(2);
+/// let mut picked: Vec<usize> = (0..24).map(|_| bag.next(12, &mut rng)).collect(); // Pentominoes
+/// picked.sort();
+/// assert_eq!(picked, (0..24).map(|i| i / 2).collect::<Vec<usize>>());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Bag {
+    copies: usize,
+    n: usize,
+    bag: Vec<usize>,
+}
+
+impl Bag {
+    /// Creates a bag holding `copies` copies of every piece.
+    pub fn new(copies: usize) -> Self {
+        Self {
+            copies: copies.max(1),
+            n: 0,
+            bag: Vec::new(),
+        }
+    }
+
+    /// Returns the number of copies of every piece in a full bag.
+    pub fn copies(&self) -> usize { self.copies }
+}
+
+impl Default for Bag {
+    fn default() -> Self { Self::new(1) }
+}
+
+impl Randomizer for Bag {
+    fn id(&self) -> &'static str { "bag" }
+
+    fn next(&mut self, n: usize, rng: &mut dyn RngCore) -> usize {
+        if n != self.n {
+            self.n = n;
+            self.bag.clear();
+        }
+        if self.bag.is_empty() {
+            self.bag = (0..n * self.copies).map(|i| i % n).collect();
+            self.bag.shuffle(rng);
+        }
+        self.bag.pop().unwrap()
+    }
+
+    fn restarted(&self) -> Box<dyn Randomizer> { Box::new(Self::new(self.copies)) }
+}
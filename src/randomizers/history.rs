@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+
+use rand::{Rng, RngCore};
+
+use crate::piece::shape::{Shape, Tetromino};
+use crate::randomizer::Randomizer;
+
+/// Remembers the last pieces picked, and rerolls a number of times while the piece picked is one of them, as in the
+/// TGM series. The last roll is kept even if it's in the history.
+///
+/// ```
+/// use atris::randomizer::Randomizer;
+/// use atris::randomizers::History;
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let mut rng = StdRng::seed_from_u64(0);
+/// let mut history = History::new(2, 1000);
+/// history.set_first_excluded(vec!(0));
+/// let picked: Vec<usize> = (0..50).map(|_| history.next(3, &mut rng)).collect();
+/// assert_ne!(picked[0], 0);
+/// // With so many rolls, every piece is different from the two before it
+/// assert!(picked.windows(3).all(|w| w[2] != w[0] && w[2] != w[1]));
+/// ```
+#[derive(Clone, Debug)]
+pub struct History {
+    size: usize,
+    rolls: u32,
+    initial_history: Vec<usize>,
+    first_excluded: Vec<usize>,
+    history: VecDeque<usize>,
+    first: bool,
+}
+
+impl History {
+    /// Creates a randomizer remembering the last `size` pieces, that picks up to `rolls` times (at least once).
+    pub fn new(size: usize, rolls: u32) -> Self {
+        Self {
+            size,
+            rolls: rolls.max(1),
+            initial_history: Vec::new(),
+            first_excluded: Vec::new(),
+            history: VecDeque::new(),
+            first: true,
+        }
+    }
+
+    /// Creates the randomizer of TGM: a history of 4 pieces that starts full of Z, 4 rolls, and a first piece that is
+    /// never S, Z or O. `shapes` are the possible pieces, in the order their indices refer to.
+    ///
+    /// ```
+    /// use atris::piece::shape::{Shapes, Tetromino};
+    /// use atris::randomizer::Randomizer;
+    /// use atris::randomizers::History;
+    /// use rand::{rngs::StdRng, SeedableRng};
+    ///
+    /// let mut shapes = Shapes::new();
+    /// shapes.gen_until(4);
+    /// let tetrominoes = shapes.shapes(4);
+    /// for seed in 0..20 {
+    ///     let mut history = History::tgm(tetrominoes);
+    ///     let first = history.next(7, &mut StdRng::seed_from_u64(seed));
+    ///     assert!(![Tetromino::S, Tetromino::Z, Tetromino::O].contains(&Tetromino::of(&tetrominoes[first]).unwrap()));
+    /// }
+    /// ```
+    pub fn tgm(shapes: &[Shape]) -> Self {
+        let index = |tetromino: Tetromino| shapes.iter().position(|shape| Tetromino::of(shape) == Some(tetromino));
+        let mut history = Self::new(4, 4);
+        history.set_initial_history(index(Tetromino::Z).map(|z| vec!(z; 4)).unwrap_or_default());
+        history.set_first_excluded([Tetromino::S, Tetromino::Z, Tetromino::O].into_iter().filter_map(index).collect());
+        history
+    }
+
+    /// Sets the pieces the history starts with, from the oldest to the newest, and starts over.
+    pub fn set_initial_history(&mut self, initial_history: Vec<usize>) {
+        self.initial_history = initial_history;
+        self.restart();
+    }
+
+    /// Sets the pieces that are never picked first.
+    pub fn set_first_excluded(&mut self, first_excluded: Vec<usize>) {
+        self.first_excluded = first_excluded;
+    }
+
+    /// Returns the last pieces picked, from the oldest to the newest.
+    pub fn history(&self) -> &VecDeque<usize> { &self.history }
+
+    fn restart(&mut self) {
+        self.history = self.initial_history.iter().copied().collect();
+        while self.history.len() > self.size {
+            self.history.pop_front();
+        }
+        self.first = true;
+    }
+}
+
+impl Randomizer for History {
+    fn id(&self) -> &'static str { "history" }
+
+    fn next(&mut self, n: usize, rng: &mut dyn RngCore) -> usize {
+        let picked = if self.first {
+            self.first = false;
+            let allowed: Vec<usize> = (0..n).filter(|i| !self.first_excluded.contains(i)).collect();
+            match allowed.is_empty() {
+                true => rng.gen_range(0..n),
+                false => allowed[rng.gen_range(0..allowed.len())],
+            }
+        } else {
+            let mut picked = rng.gen_range(0..n);
+            for _ in 1..self.rolls {
+                if !self.history.contains(&picked) {
+                    break;
+                }
+                picked = rng.gen_range(0..n);
+            }
+            picked
+        };
+        self.history.push_back(picked);
+        if self.history.len() > self.size {
+            self.history.pop_front();
+        }
+        picked
+    }
+
+    fn restarted(&self) -> Box<dyn Randomizer> {
+        let mut history = self.clone();
+        history.restart();
+        Box::new(history)
+    }
+}
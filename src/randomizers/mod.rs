@@ -0,0 +1,8 @@
+pub mod bag;
+pub mod history;
+pub mod purerandom;
+// Add new `Randomizer` structs modules here
+
+pub use bag::Bag;
+pub use history::History;
+pub use purerandom::PureRandom;
@@ -0,0 +1,17 @@
+use rand::{Rng, RngCore};
+
+use crate::randomizer::Randomizer;
+
+/// Picks every piece uniformly at random, independently of the previous ones.
+#[derive(Clone, Debug, Default)]
+pub struct PureRandom {}
+
+impl Randomizer for PureRandom {
+    fn id(&self) -> &'static str { "pure_random" }
+
+    fn next(&mut self, n: usize, rng: &mut dyn RngCore) -> usize {
+        rng.gen_range(0..n)
+    }
+
+    fn restarted(&self) -> Box<dyn Randomizer> { Box::new(self.clone()) }
+}
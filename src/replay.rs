@@ -0,0 +1,707 @@
+use std::{fmt, hash::Hasher};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{algebra::{Vector, UVector}, gamemode::GameMode, gridengine::GridEngine};
+use crate::{event::SessionEvent, input::{Action, HandlingConfig, PlayerInput}, physics::{LockReset, PhysicsConfig}};
+use crate::{grid::{Spawn, SpawnRules, SpinRule}, gridsession::{GameOverRules, GridConfig, GridSeeds}};
+use crate::{attack::AttackTable, garbage::GarbageStyle, level::LevelProgression, piece::shape::Shape};
+
+/// The 64-bit FNV-1a hash, which gives the same values on every platform and version of Rust.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Fnv1a(u64);
+
+impl Fnv1a {
+    pub fn new() -> Self { Self(0xcbf2_9ce4_8422_2325) }
+}
+
+impl Default for Fnv1a {
+    fn default() -> Self { Self::new() }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 { self.0 }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    // Integers are hashed little-endian, whatever the platform
+    fn write_u32(&mut self, i: u32) { self.write(&i.to_le_bytes()) }
+
+    fn write_u64(&mut self, i: u64) { self.write(&i.to_le_bytes()) }
+
+    fn write_i32(&mut self, i: i32) { self.write(&i.to_le_bytes()) }
+}
+
+/// Why a replay couldn't be loaded or played.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The data doesn't start like a replay.
+    NotAReplay,
+    /// The replay was made with another version of the format.
+    UnsupportedVersion(u8),
+    /// The data ends in the middle of the replay.
+    Truncated,
+    /// The data has a value that doesn't mean anything where it is.
+    Invalid(&'static str),
+    /// The game mode given to play a grid isn't the one it was recorded with.
+    GameModeMismatch { grid: usize },
+    /// A grid didn't end in the same state as when it was recorded.
+    ChecksumMismatch { grid: usize, expected: u64, found: u64 },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAReplay => write!(f, "not a replay"),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported version {version} of the replay format"),
+            Self::Truncated => write!(f, "the replay is truncated"),
+            Self::Invalid(what) => write!(f, "invalid {what} in the replay"),
+            Self::GameModeMismatch { grid } => write!(f, "grid {grid} was recorded with another game mode"),
+            Self::ChecksumMismatch { grid, expected, found } => {
+                write!(f, "grid {grid} ended with checksum {found:#018x} instead of {expected:#018x}")
+            },
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// Returns a fingerprint of a game mode (already initialized): the ids of its randomizer, rotation system and
+/// scoring rule, its level curve, and the first pieces it gives with a fixed seed, with their orientations and
+/// kicks. Game modes playing differently have different fingerprints.
+///
+/// ```
+/// use atris::gamemode::GameMode;
+/// use atris::gamemodes::standard::StandardMode;
+/// use atris::replay::game_mode_fingerprint;
+/// use atris::rotationsystems::NintendoRotationSystem;
+/// use rand::rngs::StdRng;
+///
+/// let mut game_mode = StandardMode::new();
+/// GameMode::<StdRng>::initialize(&mut game_mode);
+/// let fingerprint = game_mode_fingerprint(&game_mode);
+/// assert_eq!(game_mode_fingerprint(&game_mode), fingerprint);
+/// game_mode.set_rotation_system(Box::new(NintendoRotationSystem::new()));
+/// assert_ne!(game_mode_fingerprint(&game_mode), fingerprint);
+/// ```
+pub fn game_mode_fingerprint(game_mode: &dyn GameMode<StdRng>) -> u64 {
+    let mut hasher = Fnv1a::new();
+    let rotation_system = game_mode.rotation_system();
+    let scoring_rule = game_mode.scoring_rule();
+    let mut randomizer = game_mode.randomizer();
+    for id in [randomizer.id(), rotation_system.id(), scoring_rule.id()] {
+        hasher.write(id.as_bytes());
+        hasher.write_u8(0);
+    }
+    hasher.write_u64(scoring_rule.difficult_lines() as u64);
+    let shape = |hasher: &mut Fnv1a, shape: &Shape| {
+        hasher.write_u32(shape.len() as u32);
+        for p in shape.iter() {
+            hasher.write_i32(p.0);
+            hasher.write_i32(p.1);
+        }
+    };
+    match game_mode.level_curve() {
+        Some(curve) => {
+            hasher.write_u8(1);
+            hasher.write_u8(match curve.progression() {
+                LevelProgression::Lines(_) => 0,
+                LevelProgression::Time(_) => 1,
+            });
+            hasher.write_u64(match curve.progression() {
+                LevelProgression::Lines(n) | LevelProgression::Time(n) => n,
+            });
+            hasher.write_u32(curve.start_level());
+            hasher.write_u32(curve.max_level());
+            for &(level, speed) in curve.speeds() {
+                let values = [level, speed.gravity, speed.gravity_frames, speed.lock_delay, speed.are, speed.clear_delay];
+                for value in values {
+                    hasher.write_u32(value);
+                }
+            }
+        },
+        None => hasher.write_u8(0),
+    }
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..64 {
+        let piece = game_mode.next_piece(randomizer.as_mut(), &mut rng);
+        for block in piece.blocks() {
+            hasher.write(block.block_type.id().as_bytes());
+            hasher.write_u8(0);
+        }
+        for orientation in rotation_system.orientations(piece.shape()).iter() {
+            shape(&mut hasher, orientation);
+        }
+        for (from, to) in (0..4).flat_map(|from| [1, 2, 3].map(|angle| (from, (from + angle) % 4))) {
+            let kicks = rotation_system.kicks(piece.shape(), from, to);
+            hasher.write_u32(kicks.len() as u32);
+            for kick in kicks {
+                hasher.write_i32(kick.0);
+                hasher.write_i32(kick.1);
+            }
+        }
+    }
+    hasher.finish()
+}
+
+/// The recording of a grid: how it was created, with a fingerprint of its game mode and its configuration, and the
+/// messages its engine got on every step.
+///
+/// ```
+/// use atris::algebra::Vector;
+/// use atris::event::SessionEvent;
+/// use atris::gamemode::GameMode;
+/// use atris::gamemodes::standard::StandardMode;
+/// use atris::gridengine::GridEngine;
+/// use atris::gridsession::{GridConfig, GridSeeds};
+/// use atris::input::{Action, HandlingConfig, PlayerInput};
+/// use atris::replay::GridReplay;
+/// use rand::rngs::StdRng;
+///
+/// let mut game_mode = StandardMode::new();
+/// GameMode::<StdRng>::initialize(&mut game_mode);
+/// let seeds = GridSeeds { pieces: 3, grid: 4 };
+/// let mut engine = GridEngine::new(1, Vector(10, 20), &game_mode, GridConfig::default(), seeds);
+/// let mut replay = GridReplay::new(1, Vector(10, 20), &game_mode, GridConfig::default(), seeds);
+/// engine.start();
+/// for tick in 0..100 {
+///     let mut messages = Vec::new();
+///     if tick == 0 {
+///         messages.push(SessionEvent::PlayerAdded(0, HandlingConfig::default()));
+///     } else if tick % 10 == 0 {
+///         messages.push(SessionEvent::Input(0, PlayerInput::Press(Action::HardDrop)));
+///     }
+///     replay.record(&messages);
+///     engine.step(messages);
+/// }
+/// replay.finish(&engine);
+/// let replayed = replay.play(&game_mode).unwrap();
+/// assert_eq!(replayed.score(), engine.score());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridReplay {
+    id: usize,
+    bounds: UVector,
+    /// The fingerprint of the game mode, given by `game_mode_fingerprint`.
+    game_mode: u64,
+    config: GridConfig,
+    seeds: GridSeeds,
+    /// The steps that got messages, with their index.
+    steps: Vec<(u64, Vec<SessionEvent>)>,
+    n_steps: u64,
+    checksum: u64,
+}
+
+impl GridReplay {
+    /// Starts the recording of a grid, created with the given id, visible bounds, game mode, configuration and seeds.
+    pub fn new(grid_id: usize, grid_bounds: UVector, game_mode: &dyn GameMode<StdRng>, config: GridConfig,
+            seeds: GridSeeds) -> Self {
+        Self {
+            id: grid_id,
+            bounds: grid_bounds,
+            game_mode: game_mode_fingerprint(game_mode),
+            config,
+            seeds,
+            steps: Vec::new(),
+            n_steps: 0,
+            checksum: 0,
+        }
+    }
+
+    pub fn id(&self) -> usize { self.id }
+
+    pub fn bounds(&self) -> UVector { self.bounds }
+
+    /// Returns the fingerprint of the game mode the grid was played with.
+    pub fn game_mode(&self) -> u64 { self.game_mode }
+
+    /// Returns the configuration the grid was played with.
+    pub fn config(&self) -> &GridConfig { &self.config }
+
+    pub fn seeds(&self) -> GridSeeds { self.seeds }
+
+    /// Returns the number of steps recorded.
+    pub fn n_steps(&self) -> u64 { self.n_steps }
+
+    /// Returns the checksum of the grid at the end of the recording.
+    pub fn checksum(&self) -> u64 { self.checksum }
+
+    /// Records the messages given to the next step of the engine.
+    pub fn record(&mut self, messages: &[SessionEvent]) {
+        if !messages.is_empty() {
+            self.steps.push((self.n_steps, messages.to_vec()));
+        }
+        self.n_steps += 1;
+    }
+
+    /// Ends the recording, keeping the checksum of the engine to verify the playback.
+    pub fn finish(&mut self, engine: &GridEngine) {
+        self.checksum = engine.checksum();
+    }
+
+    /// Plays the recording again, from the start of the game, with the game mode it was recorded with. Returns the
+    /// engine in its final state, or an error if the game mode isn't the one recorded or the final state doesn't match
+    /// the checksum recorded.
+    pub fn play<'a>(&self, game_mode: &'a dyn GameMode<StdRng>) -> Result<GridEngine<'a>, ReplayError> {
+        if game_mode_fingerprint(game_mode) != self.game_mode {
+            return Err(ReplayError::GameModeMismatch { grid: self.id });
+        }
+        let mut engine = GridEngine::new(self.id, self.bounds, game_mode, self.config.clone(), self.seeds);
+        engine.start();
+        let mut steps = self.steps.iter().peekable();
+        for step in 0..self.n_steps {
+            let messages = match steps.next_if(|(i, _)| *i == step) {
+                Some((_, messages)) => messages.clone(),
+                None => Vec::new(),
+            };
+            engine.step(messages);
+        }
+        let found = engine.checksum();
+        if found != self.checksum {
+            return Err(ReplayError::ChecksumMismatch { grid: self.id, expected: self.checksum, found });
+        }
+        Ok(engine)
+    }
+}
+
+/// The recording of a whole session: the seed it was played with, and the recording of every grid. The game mode isn't
+/// recorded, only its fingerprint, so the same one must be given to play it.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Replay {
+    pub seed: u64,
+    pub grids: Vec<GridReplay>,
+}
+
+impl Replay {
+    /// Magic bytes at the start of the format.
+    const MAGIC: &'static [u8] = b"ATRP";
+    /// Version of the format, written right after the magic bytes.
+    const VERSION: u8 = 2;
+
+    /// Plays all the grids again, and returns their engines in their final state, or the first checksum mismatch.
+    pub fn play<'a>(&self, game_mode: &'a dyn GameMode<StdRng>) -> Result<Vec<GridEngine<'a>>, ReplayError> {
+        self.grids.iter().map(|grid| grid.play(game_mode)).collect()
+    }
+
+    /// Encodes the replay in a compact binary format: integers are variable-length, and only the steps with messages
+    /// are stored.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::event::SessionEvent;
+    /// use atris::gridsession::{GridConfig, GridSeeds};
+    /// use atris::gamemode::GameMode;
+    /// use atris::gamemodes::standard::StandardMode;
+    /// use atris::input::{Action, PlayerInput};
+    /// use atris::replay::{GridReplay, Replay};
+    /// use rand::rngs::StdRng;
+    ///
+    /// let mut game_mode = StandardMode::new();
+    /// GameMode::<StdRng>::initialize(&mut game_mode);
+    /// let seeds = GridSeeds { pieces: 1, grid: 2 };
+    /// let mut grid = GridReplay::new(7, Vector(10, 20), &game_mode, GridConfig::default(), seeds);
+    /// grid.record(&[SessionEvent::Input(0, PlayerInput::Press(Action::Left))]);
+    /// grid.record(&[]);
+    /// let replay = Replay { seed: 42, grids: vec!(grid) };
+    /// let bytes = replay.to_bytes();
+    /// let loaded = Replay::from_bytes(&bytes).unwrap();
+    /// assert_eq!(loaded.grids, replay.grids);
+    /// assert_eq!(loaded.to_bytes(), bytes);
+    /// assert!(Replay::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer(Self::MAGIC.to_vec());
+        w.0.push(Self::VERSION);
+        w.u64(self.seed);
+        w.u64(self.grids.len() as u64);
+        for grid in self.grids.iter() {
+            w.u64(grid.id as u64);
+            w.u64(grid.bounds.0 as u64);
+            w.u64(grid.bounds.1 as u64);
+            w.fixed(grid.game_mode);
+            w.config(&grid.config);
+            w.fixed(grid.seeds.pieces);
+            w.fixed(grid.seeds.grid);
+            w.u64(grid.n_steps);
+            w.u64(grid.steps.len() as u64);
+            let mut last = 0;
+            for (step, messages) in grid.steps.iter() {
+                w.u64(step - last);
+                last = *step;
+                w.u64(messages.len() as u64);
+                for e in messages.iter() {
+                    w.event(e);
+                }
+            }
+            w.fixed(grid.checksum);
+        }
+        w.0
+    }
+
+    /// Decodes a replay encoded with `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        if !bytes.starts_with(Self::MAGIC) {
+            return Err(ReplayError::NotAReplay);
+        }
+        let mut r = Reader { bytes, pos: Self::MAGIC.len() };
+        let version = r.byte()?;
+        if version != Self::VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let seed = r.u64()?;
+        let n_grids = r.u64()?;
+        let mut grids = Vec::new();
+        for _ in 0..n_grids {
+            let id = r.usize()?;
+            let bounds = Vector(r.usize()?, r.usize()?);
+            let game_mode = r.fixed()?;
+            let config = r.config()?;
+            let seeds = GridSeeds { pieces: r.fixed()?, grid: r.fixed()? };
+            let n_steps = r.u64()?;
+            let mut steps = Vec::new();
+            let mut step = 0u64;
+            for _ in 0..r.u64()? {
+                step = step.checked_add(r.u64()?).ok_or(ReplayError::Invalid("step"))?;
+                let messages = (0..r.u64()?).map(|_| r.event()).collect::<Result<Vec<_>, _>>()?;
+                steps.push((step, messages));
+            }
+            if steps.last().is_some_and(|&(step, _)| step >= n_steps) {
+                return Err(ReplayError::Invalid("step"));
+            }
+            let checksum = r.fixed()?;
+            grids.push(GridReplay { id, bounds, game_mode, config, seeds, steps, n_steps, checksum });
+        }
+        if r.pos != bytes.len() {
+            return Err(ReplayError::Invalid("trailing data"));
+        }
+        Ok(Self { seed, grids })
+    }
+}
+
+const ACTIONS: [Action; 8] = [Action::Left, Action::Right, Action::SoftDrop, Action::HardDrop, Action::RotateCW,
+    Action::RotateCCW, Action::Rotate180, Action::Hold];
+
+/// Writes values in the replay format.
+struct Writer(Vec<u8>);
+
+impl Writer {
+    /// Writes an unsigned integer as LEB128: 7 bits per byte, the highest bit telling whether more bytes follow.
+    fn u64(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    /// Writes a signed integer, zigzag encoded so small negative values stay short.
+    fn i64(&mut self, value: i64) {
+        self.u64(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    /// Writes a value that is rarely small, like seeds and checksums, in 8 bytes.
+    fn fixed(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.0.push(value as u8);
+    }
+
+    fn u32s(&mut self, values: &[u32]) {
+        self.u64(values.len() as u64);
+        for &value in values {
+            self.u64(value as u64);
+        }
+    }
+
+    fn handling(&mut self, handling: &HandlingConfig) {
+        self.u64(handling.das as u64);
+        self.u64(handling.arr as u64);
+        self.u64(handling.soft_drop_factor as u64);
+    }
+
+    fn event(&mut self, e: &SessionEvent) {
+        match e {
+            SessionEvent::Start => self.u64(0),
+            SessionEvent::Pause => self.u64(1),
+            SessionEvent::Resume => self.u64(2),
+            SessionEvent::Stop => self.u64(3),
+            SessionEvent::PlayerAdded(player_id, handling) => {
+                self.u64(4);
+                self.u64(*player_id as u64);
+                self.handling(handling);
+            },
+            SessionEvent::Handling(player_id, handling) => {
+                self.u64(5);
+                self.u64(*player_id as u64);
+                self.handling(handling);
+            },
+            SessionEvent::Input(player_id, input) => {
+                self.u64(6);
+                self.u64(*player_id as u64);
+                // The lowest bit tells whether the key was pressed, the others which action it is
+                let (action, pressed) = match input {
+                    PlayerInput::Press(action) => (action, 1),
+                    PlayerInput::Release(action) => (action, 0),
+                };
+                self.u64((ACTIONS.iter().position(|a| a == action).unwrap() as u64) << 1 | pressed);
+            },
+            SessionEvent::GridAdded(grid_id) => {
+                self.u64(7);
+                self.u64(*grid_id as u64);
+            },
+            SessionEvent::GridRemoved(grid_id) => {
+                self.u64(8);
+                self.u64(*grid_id as u64);
+            },
+            SessionEvent::Garbage(lines) => {
+                self.u64(9);
+                self.u64(*lines as u64);
+            },
+            SessionEvent::Speed(speed) => {
+                self.u64(10);
+                self.u64(*speed as u64);
+            },
+            SessionEvent::FrameStepping(frame_stepping) => {
+                self.u64(11);
+                self.bool(*frame_stepping);
+            },
+            SessionEvent::StepFrame => self.u64(12),
+        }
+    }
+
+    fn config(&mut self, config: &GridConfig) {
+        self.u64(config.tps);
+        let physics = &config.physics;
+        self.u64(physics.gravity as u64);
+        self.u64(physics.gravity_ticks as u64);
+        self.u64(physics.lock_delay as u64);
+        match physics.lock_reset {
+            LockReset::Infinity => self.u64(0),
+            LockReset::MoveReset(max_resets) => {
+                self.u64(1);
+                self.u64(max_resets as u64);
+            },
+            LockReset::StepReset => self.u64(2),
+        }
+        self.u64(physics.are as u64);
+        self.u64(physics.clear_delay as u64);
+        self.u64(config.hidden_rows as u64);
+        let spawns: Vec<&(Shape, Spawn)> = config.spawn.iter().collect();
+        self.u64(spawns.len() as u64);
+        for (shape, spawn) in spawns {
+            self.u64(shape.len() as u64);
+            for pos in shape.iter() {
+                self.i64(pos.0 as i64);
+                self.i64(pos.1 as i64);
+            }
+            self.i64(spawn.offset.0 as i64);
+            self.i64(spawn.offset.1 as i64);
+            self.i64(spawn.rotation as i64);
+        }
+        self.u64(config.preview as u64);
+        self.bool(config.hold);
+        self.bool(config.ihs);
+        self.bool(config.irs);
+        self.bool(config.game_over.block_out);
+        self.bool(config.game_over.lock_out);
+        self.bool(config.game_over.top_out);
+        self.u64(match config.spin_rule {
+            SpinRule::None => 0,
+            SpinRule::TSpin => 1,
+            SpinRule::AllSpin => 2,
+        });
+        let attack = &config.attack;
+        self.u32s(&attack.lines);
+        self.u32s(&attack.spin);
+        self.u32s(&attack.mini_spin);
+        self.u32s(&attack.combo);
+        self.u64(attack.back_to_back as u64);
+        self.u64(attack.perfect_clear as u64);
+        self.u64(config.garbage_delay as u64);
+        self.u64(config.garbage_cap as u64);
+        match config.garbage_style {
+            GarbageStyle::Clean => self.u64(0),
+            GarbageStyle::Messy(chance) => {
+                self.u64(1);
+                self.u64(chance as u64);
+            },
+            GarbageStyle::Cheese => self.u64(2),
+            GarbageStyle::MultiHole(holes) => {
+                self.u64(3);
+                self.u64(holes as u64);
+            },
+        }
+    }
+}
+
+/// Reads values in the replay format.
+struct Reader<'b> {
+    bytes: &'b [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, ReplayError> {
+        let byte = *self.bytes.get(self.pos).ok_or(ReplayError::Truncated)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    /// Reads an unsigned integer written as LEB128. Its tenth byte, holding the highest bit, can only be 0 or 1.
+    fn u64(&mut self) -> Result<u64, ReplayError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            if shift == 63 && byte > 1 {
+                return Err(ReplayError::Invalid("integer"));
+            }
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ReplayError::Invalid("integer"))
+    }
+
+    fn u32(&mut self) -> Result<u32, ReplayError> {
+        self.u64()?.try_into().map_err(|_| ReplayError::Invalid("integer"))
+    }
+
+    fn usize(&mut self) -> Result<usize, ReplayError> {
+        self.u64()?.try_into().map_err(|_| ReplayError::Invalid("integer"))
+    }
+
+    fn i32(&mut self) -> Result<i32, ReplayError> {
+        let value = self.u64()?;
+        let value = (value >> 1) as i64 ^ -((value & 1) as i64);
+        value.try_into().map_err(|_| ReplayError::Invalid("integer"))
+    }
+
+    fn fixed(&mut self) -> Result<u64, ReplayError> {
+        let bytes = self.bytes.get(self.pos..self.pos + 8).ok_or(ReplayError::Truncated)?;
+        self.pos += 8;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn bool(&mut self) -> Result<bool, ReplayError> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(ReplayError::Invalid("boolean")),
+        }
+    }
+
+    fn u32s(&mut self) -> Result<Vec<u32>, ReplayError> {
+        (0..self.u64()?).map(|_| self.u32()).collect()
+    }
+
+    fn handling(&mut self) -> Result<HandlingConfig, ReplayError> {
+        Ok(HandlingConfig { das: self.u32()?, arr: self.u32()?, soft_drop_factor: self.u32()? })
+    }
+
+    fn event(&mut self) -> Result<SessionEvent, ReplayError> {
+        Ok(match self.u64()? {
+            0 => SessionEvent::Start,
+            1 => SessionEvent::Pause,
+            2 => SessionEvent::Resume,
+            3 => SessionEvent::Stop,
+            4 => SessionEvent::PlayerAdded(self.usize()?, self.handling()?),
+            5 => SessionEvent::Handling(self.usize()?, self.handling()?),
+            6 => {
+                let player_id = self.usize()?;
+                let input = self.u64()?;
+                let action = *ACTIONS.get((input >> 1) as usize).ok_or(ReplayError::Invalid("action"))?;
+                SessionEvent::Input(player_id, if input & 1 == 1 {
+                    PlayerInput::Press(action)
+                } else {
+                    PlayerInput::Release(action)
+                })
+            },
+            7 => SessionEvent::GridAdded(self.usize()?),
+            8 => SessionEvent::GridRemoved(self.usize()?),
+            9 => SessionEvent::Garbage(self.u32()?),
+            10 => SessionEvent::Speed(self.u32()?),
+            11 => SessionEvent::FrameStepping(self.bool()?),
+            12 => SessionEvent::StepFrame,
+            _ => return Err(ReplayError::Invalid("message")),
+        })
+    }
+
+    fn config(&mut self) -> Result<GridConfig, ReplayError> {
+        let tps = self.u64()?;
+        let gravity = self.u32()?;
+        let gravity_ticks = self.u32()?;
+        let lock_delay = self.u32()?;
+        let lock_reset = match self.u64()? {
+            0 => LockReset::Infinity,
+            1 => LockReset::MoveReset(self.u32()?),
+            2 => LockReset::StepReset,
+            _ => return Err(ReplayError::Invalid("lock reset")),
+        };
+        let physics = PhysicsConfig {
+            gravity,
+            gravity_ticks,
+            lock_delay,
+            lock_reset,
+            are: self.u32()?,
+            clear_delay: self.u32()?,
+        };
+        let hidden_rows = self.usize()?;
+        let mut spawn = SpawnRules::new();
+        for _ in 0..self.u64()? {
+            let shape = (0..self.u64()?).map(|_| Ok(Vector(self.i32()?, self.i32()?)))
+                .collect::<Result<Shape, ReplayError>>()?;
+            spawn.set(shape, Spawn { offset: Vector(self.i32()?, self.i32()?), rotation: self.i32()? });
+        }
+        let preview = self.usize()?;
+        let (hold, ihs, irs) = (self.bool()?, self.bool()?, self.bool()?);
+        let game_over = GameOverRules { block_out: self.bool()?, lock_out: self.bool()?, top_out: self.bool()? };
+        let spin_rule = match self.u64()? {
+            0 => SpinRule::None,
+            1 => SpinRule::TSpin,
+            2 => SpinRule::AllSpin,
+            _ => return Err(ReplayError::Invalid("spin rule")),
+        };
+        let attack = AttackTable {
+            lines: self.u32s()?,
+            spin: self.u32s()?,
+            mini_spin: self.u32s()?,
+            combo: self.u32s()?,
+            back_to_back: self.u32()?,
+            perfect_clear: self.u32()?,
+        };
+        let garbage_delay = self.u32()?;
+        let garbage_cap = self.u32()?;
+        let garbage_style = match self.u64()? {
+            0 => GarbageStyle::Clean,
+            1 => GarbageStyle::Messy(self.u32()?),
+            2 => GarbageStyle::Cheese,
+            3 => GarbageStyle::MultiHole(self.usize()?),
+            _ => return Err(ReplayError::Invalid("garbage style")),
+        };
+        Ok(GridConfig {
+            tps,
+            physics,
+            hidden_rows,
+            spawn,
+            preview,
+            hold,
+            ihs,
+            irs,
+            game_over,
+            spin_rule,
+            attack,
+            garbage_delay,
+            garbage_cap,
+            garbage_style,
+        })
+    }
+}
@@ -0,0 +1,294 @@
+use std::{fmt::Debug, sync::Mutex};
+
+use crate::algebra::{Vector, IVector, FVector};
+use crate::piece::shape::Shape;
+
+/// This `trait` represents how pieces rotate: the orientation they spawn with, the point they rotate around and which
+/// offsets (kicks) are tried when a rotation is obstructed.
+///
+/// Rotation states are numbered by counter-clockwise quarter turns from the spawn orientation, so 0 is the spawn
+/// orientation, 1 is the one reached rotating counter-clockwise once, and 3 the one reached rotating clockwise once.
+pub trait RotationSystem: Send + Sync + Debug {
+    /// Returns the identifier (an unique name) of the `RotationSystem` object. The value returned must be constant all
+    /// the time.
+    fn id(&self) -> &'static str;
+
+    /// Returns `shape` in the orientation it spawns with, relative to the position of the `Figure`.
+    fn spawn_shape(&self, shape: &Shape) -> Shape;
+
+    /// Returns the point that `spawn_shape` (already in its spawn orientation) rotates around. It must be the center
+    /// of a cell or a corner between cells.
+    fn pivot(&self, spawn_shape: &Shape) -> FVector { center_pivot(spawn_shape) }
+
+    /// Returns the shapes of the 4 rotation states of `shape`, relative to the position of the `Figure`. By default,
+    /// they are the spawn shape rotated around its pivot.
+    fn orientations(&self, shape: &Shape) -> Vec<Shape> {
+        let spawn_shape = self.spawn_shape(shape);
+        let pivot = self.pivot(&spawn_shape);
+        (0..4).map(|angle| spawn_shape.rotated_around(angle, pivot)).collect()
+    }
+
+    /// Returns the kick tables of the `RotationSystem`, with the ones overridden and the ones generated so far.
+    fn kick_tables(&self) -> &KickTables;
+
+    fn kick_tables_mut(&mut self) -> &mut KickTables;
+
+    /// Overrides the kicks of `shape` (in any orientation) with `kick_table`, where the rotation state 0 is the spawn
+    /// orientation. The method returns `Some<kt>` if some kick table `kt` was overridden before for the same shape.
+    /// Otherwise, it returns `None`.
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::piece::shape::Tetromino;
+    /// use atris::rotationsystem::{KickTable, RotationSystem};
+    /// use atris::rotationsystems::{ArikaRotationSystem, SuperRotationSystem};
+    ///
+    /// let mut kicks = KickTable::new();
+    /// kicks.set(0, 3, vec!(Vector(0, 0), Vector(0, 1)));
+    /// let mut srs = SuperRotationSystem::new();
+    /// assert_eq!(srs.set_kick_table(Tetromino::T.shape(), kicks.clone()), None);
+    /// assert_eq!(srs.kicks(&Tetromino::T.shape().rotated(2), 0, 3), vec!(Vector(0, 0), Vector(0, 1)));
+    /// assert_eq!(srs.set_kick_table(Tetromino::T.shape(), KickTable::new()), Some(kicks.clone()));
+    /// let mut ars = ArikaRotationSystem::new();
+    /// ars.set_kick_table(Tetromino::I.shape(), kicks);
+    /// assert_eq!(ars.kicks(&Tetromino::I.shape(), 0, 3), vec!(Vector(0, 0), Vector(0, 1)));
+    /// ```
+    fn set_kick_table(&mut self, shape: Shape, kick_table: KickTable) -> Option<KickTable> {
+        self.kick_tables_mut().set(shape, kick_table)
+    }
+
+    /// Returns the offsets to test, in order, when rotating `shape` from the rotation state `from` to the rotation state
+    /// `to`. The first one is usually `Vector(0, 0)`, this is, rotating in place. These are the ones overridden with
+    /// `set_kick_table`, if any, or the default ones.
+    fn kicks(&self, shape: &Shape, from: i32, to: i32) -> Vec<IVector> {
+        match self.kick_tables().overridden(shape) {
+            Some(kick_table) => kick_table.get(from, to).to_vec(),
+            None => self.default_kicks(shape, from, to),
+        }
+    }
+
+    /// Returns the offsets to test when rotating `shape` from the rotation state `from` to the rotation state `to`, if
+    /// its kicks aren't overridden.
+    fn default_kicks(&self, shape: &Shape, from: i32, to: i32) -> Vec<IVector>;
+
+    /// Checks whether kicks other than the first one can be tried, once the first one failed. `obstructed` are the
+    /// points of the rotated shape (relative to the position of the `Figure`) that were out of bounds or overlapped
+    /// other blocks when trying the first kick.
+    fn kick_allowed(&self, _shape: &Shape, _from: i32, _to: i32, _obstructed: &[IVector]) -> bool { true }
+}
+
+/// Returns the center of the bounds of `shape`, rounded to the nearest cell center or corner between cells.
+pub fn center_pivot(shape: &Shape) -> FVector {
+    let center = shape.f64_center();
+    Vector((center.0 * 2.0).round() / 2.0, (center.1 * 2.0).round() / 2.0)
+}
+/// Returns `shape` in its flattest orientation: the one with the smallest height and, among those, the one with the
+/// most points in its bottom row and then the most points in its left column. The result is centered like
+/// `Shape::centered`. Every rotation of the same `Shape` gives the same result.
+///
+/// ```
+/// use atris::algebra::Vector;
+/// use atris::piece::shape::{Shape, Tetromino};
+/// use atris::rotationsystem::flat_orientation;
+///
+/// assert_eq!(flat_orientation(&Tetromino::T.shape().rotated(1)).positions(), Tetromino::T.shape().positions());
+/// assert_eq!(flat_orientation(&Tetromino::I.shape().rotated(3)).extents(), Vector(4, 1));
+/// ```
+pub fn flat_orientation(shape: &Shape) -> Shape {
+    let key = |s: &Shape| {
+        let bounds = s.bounds();
+        let bottom = s.iter().filter(|p| p.1 == bounds.0.1).count();
+        let left = s.iter().filter(|p| p.0 == bounds.0.0).count();
+        (s.extents().1, -(bottom as i32), -(left as i32))
+    };
+    (0..4).map(|angle| shape.rotated(angle).centered())
+        .min_by_key(key)
+        .unwrap_or_default()
+}
+
+/// Kicks to test for each rotation of a shape, indexed by the rotation states before and after rotating.
+///
+/// Generated tables follow the structure of the SRS ones, scaled with the size of the shape: a shape spanning more
+/// cells is allowed to move further away from walls and the floor. The shape's symmetry decides which rotations need
+/// kicks at all: a shape that looks the same after a quarter turn never needs them, and a shape that looks the same
+/// after a half turn and spans an even number of cells also tests the opposite side, since its pivot is as close to one
+/// side as to the other.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KickTable {
+    kicks: Vec<Vec<Vec<IVector>>>,
+}
+
+impl KickTable {
+    /// Creates a new `KickTable` where every rotation just tests the rotated position (no kicks).
+    pub fn new() -> Self {
+        Self { kicks: vec!(vec!(vec!(Vector(0, 0)); 4); 4) }
+    }
+
+    /// Generates the `KickTable` for `shape`, whose rotation state 0 is its spawn orientation.
+    ///
+    /// For tetrominoes, other than the I and O ones, the quarter turns of the generated table are the SRS ones:
+    ///
+    /// ```
+    /// use atris::algebra::Vector;
+    /// use atris::piece::shape::{Shape, Tetromino};
+    /// use atris::rotationsystem::{KickTable, RotationSystem};
+    /// use atris::rotationsystems::SuperRotationSystem;
+    ///
+    /// let srs = SuperRotationSystem::new();
+    /// for tetromino in [Tetromino::T, Tetromino::S, Tetromino::Z, Tetromino::J, Tetromino::L] {
+    ///     let kicks = KickTable::generate(&tetromino.shape());
+    ///     for (from, to) in [(0, 1), (1, 0), (1, 2), (2, 1), (2, 3), (3, 2), (3, 0), (0, 3)] {
+    ///         assert_eq!(kicks.get(from, to), srs.kicks(&tetromino.shape(), from, to), "{tetromino:?} {from}->{to}");
+    ///     }
+    /// }
+    /// let t = KickTable::generate(&Tetromino::T.shape());
+    /// assert_eq!(t.get(0, 3), &[Vector(0, 0), Vector(-1, 0), Vector(-1, 1), Vector(0, -2), Vector(-1, -2)]);
+    /// assert_eq!(t.get(3, 2), &[Vector(0, 0), Vector(1, 0), Vector(1, -1), Vector(0, 2), Vector(1, 2)]);
+    /// assert_eq!(t.get(0, 2), &[Vector(0, 0), Vector(0, 1), Vector(1, 1), Vector(-1, 1), Vector(1, 0), Vector(-1, 0)]);
+    ///
+    /// // A 6-block line can move 3 cells away from a wall
+    /// let i6 = Shape::from_iter((-2..4).map(|x| Vector(x, 0)));
+    /// let kicks = KickTable::generate(&i6);
+    /// assert!(kicks.get(0, 3).contains(&Vector(-3, 0)));
+    /// assert!(kicks.get(0, 3).contains(&Vector(3, 0)));
+    ///
+    /// // The plus-shaped pentomino never needs kicks
+    /// let x = Shape::from_iter([Vector(0, 0), Vector(1, 0), Vector(-1, 0), Vector(0, 1), Vector(0, -1)]);
+    /// assert_eq!(KickTable::generate(&x), KickTable::new());
+    /// ```
+    pub fn generate(shape: &Shape) -> Self {
+        let mut table = Self::new();
+        let n_orientations = shape.n_orientations();
+        if n_orientations == 1 {
+            return table;
+        }
+        let extents = shape.extents();
+        let reach = (extents.0.max(extents.1) / 2).max(1);
+
+        // Offsets of the rotation states, like the SRS ones: none for states 0 and 2, towards the right for state 3
+        // (R) and towards the left for state 1 (L). Kicks are the difference between the offsets of both states.
+        let mut right: Vec<IVector> = vec!(Vector(0, 0));
+        right.extend((1..=reach).map(|x| Vector(x, 0)));
+        right.extend([Vector(reach, -1), Vector(0, reach + 1), Vector(reach, reach + 1)]);
+        let left: Vec<IVector> = right.iter().map(|p| Vector(-p.0, p.1)).collect();
+        let none = vec!(Vector(0, 0); right.len());
+        let offsets = [&none, &left, &none, &right];
+
+        for from in 0..4 {
+            // Quarter turns
+            for to in [(from + 1) % 4, (from + 3) % 4] {
+                let mut kicks = Vec::new();
+                for (&a, &b) in offsets[from].iter().zip(offsets[to].iter()) {
+                    push_unique(&mut kicks, a - b);
+                }
+                if n_orientations == 2 && extents.0.max(extents.1) % 2 == 0 {
+                    let opposite: Vec<IVector> = kicks.iter().filter(|p| p.1 == 0).map(|p| Vector(-p.0, 0)).collect();
+                    for p in opposite {
+                        push_unique(&mut kicks, p);
+                    }
+                }
+                table.kicks[from][to] = kicks;
+            }
+            // Half turn
+            let mut kicks = vec!(Vector(0, 0), Vector(0, 1));
+            for x in 1..=reach {
+                kicks.extend([Vector(x, 1), Vector(-x, 1), Vector(x, 0), Vector(-x, 0)]);
+            }
+            table.kicks[from][(from + 2) % 4] = kicks;
+        }
+        table
+    }
+
+    /// Returns the kicks to test when rotating from the rotation state `from` to the rotation state `to`.
+    pub fn get(&self, from: i32, to: i32) -> &[IVector] {
+        &self.kicks[from.rem_euclid(4) as usize][to.rem_euclid(4) as usize]
+    }
+
+    /// Sets the kicks to test when rotating from the rotation state `from` to the rotation state `to`.
+    pub fn set(&mut self, from: i32, to: i32, kicks: Vec<IVector>) {
+        self.kicks[from.rem_euclid(4) as usize][to.rem_euclid(4) as usize] = kicks;
+    }
+}
+
+impl Default for KickTable {
+    fn default() -> Self { Self::new() }
+}
+
+/// The kick tables of a `RotationSystem`: the ones overridden for some shapes, and the ones generated with
+/// `KickTable::generate`, which are kept so that each shape's table is only generated once.
+///
+/// ```
+/// use atris::algebra::Vector;
+/// use atris::piece::shape::{Shape, Tetromino};
+/// use atris::rotationsystem::{KickTable, KickTables};
+///
+/// let mut kick_tables = KickTables::new();
+/// let i6 = Shape::from_iter((-2..4).map(|x| Vector(x, 0)));
+/// assert_eq!(kick_tables.generated(&i6, 0, 3), KickTable::generate(&i6).get(0, 3));
+/// assert_eq!(kick_tables.generated(&i6.rotated(1), 0, 3), KickTable::generate(&i6.rotated(1)).get(0, 3));
+/// assert_eq!(kick_tables.set(Tetromino::T.shape(), KickTable::new()), None);
+/// assert_eq!(kick_tables.overridden(&Tetromino::T.shape().rotated(1)), Some(&KickTable::new()));
+/// assert_eq!(kick_tables.remove(&Tetromino::T.shape()), Some(KickTable::new()));
+/// assert_eq!(kick_tables.overridden(&Tetromino::T.shape()), None);
+/// ```
+#[derive(Debug, Default)]
+pub struct KickTables {
+    overridden: Vec<(Shape, KickTable)>,
+    /// Tables generated so far, for the normalized points of the spawn shapes they were generated for. `Shape`s
+    /// compare regardless of their rotation, but generated tables depend on the spawn orientation.
+    generated: Mutex<Vec<(Vec<IVector>, KickTable)>>,
+}
+
+impl KickTables {
+    /// Creates a new `KickTables` object, without overridden or generated tables.
+    pub fn new() -> Self {
+        Self {
+            overridden: Vec::new(),
+            generated: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the kick table overriding the kicks of `shape` (in any orientation), if any.
+    pub fn overridden(&self, shape: &Shape) -> Option<&KickTable> {
+        self.overridden.iter().find(|(s, _)| s == shape).map(|(_, kick_table)| kick_table)
+    }
+
+    /// Overrides the kicks of `shape` (in any orientation) with `kick_table`, and returns the kick table overridden
+    /// before for the same shape, if any.
+    pub fn set(&mut self, shape: Shape, kick_table: KickTable) -> Option<KickTable> {
+        match self.overridden.iter_mut().find(|(s, _)| s == &shape) {
+            Some((_, kt)) => Some(std::mem::replace(kt, kick_table)),
+            None => {
+                self.overridden.push((shape, kick_table));
+                None
+            },
+        }
+    }
+
+    /// Makes `shape` use its default kicks again, and returns the kick table it was overridden with, if any.
+    pub fn remove(&mut self, shape: &Shape) -> Option<KickTable> {
+        let i = self.overridden.iter().position(|(s, _)| s == shape)?;
+        Some(self.overridden.remove(i).1)
+    }
+
+    /// Returns the kicks from the rotation state `from` to the rotation state `to` of the table generated for
+    /// `spawn_shape`, generating it the first time.
+    pub fn generated(&self, spawn_shape: &Shape, from: i32, to: i32) -> Vec<IVector> {
+        let mut generated = self.generated.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let key = spawn_shape.normalized().positions().clone();
+        if let Some((_, kick_table)) = generated.iter().find(|(positions, _)| positions == &key) {
+            return kick_table.get(from, to).to_vec();
+        }
+        let kick_table = KickTable::generate(spawn_shape);
+        let kicks = kick_table.get(from, to).to_vec();
+        generated.push((key, kick_table));
+        kicks
+    }
+}
+
+/// Pushes `p` to `v` only if it's not there yet.
+fn push_unique(v: &mut Vec<IVector>, p: IVector) {
+    if !v.contains(&p) {
+        v.push(p);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::algebra::{Vector, IVector};
+use crate::piece::shape::{Shape, Tetromino};
+use crate::rotationsystem::{RotationSystem, KickTables};
+
+/// Cells (column, row from the top) of each tetromino in its 3x3 box (4x4 for the I piece) for the states 0, R, 2 and L
+/// (clockwise order).
+fn box_cells(tetromino: Tetromino) -> [[(i32, i32); 4]; 4] {
+    match tetromino {
+        Tetromino::I => [
+            [(0, 1), (1, 1), (2, 1), (3, 1)],
+            [(2, 0), (2, 1), (2, 2), (2, 3)],
+            [(0, 1), (1, 1), (2, 1), (3, 1)],
+            [(2, 0), (2, 1), (2, 2), (2, 3)],
+        ],
+        Tetromino::O => [[(1, 1), (2, 1), (1, 2), (2, 2)]; 4],
+        Tetromino::T => [
+            [(0, 1), (1, 1), (2, 1), (1, 2)],
+            [(1, 0), (0, 1), (1, 1), (1, 2)],
+            [(1, 1), (0, 2), (1, 2), (2, 2)],
+            [(1, 0), (1, 1), (2, 1), (1, 2)],
+        ],
+        Tetromino::S => [
+            [(1, 1), (2, 1), (0, 2), (1, 2)],
+            [(0, 0), (0, 1), (1, 1), (1, 2)],
+            [(1, 1), (2, 1), (0, 2), (1, 2)],
+            [(0, 0), (0, 1), (1, 1), (1, 2)],
+        ],
+        Tetromino::Z => [
+            [(0, 1), (1, 1), (1, 2), (2, 2)],
+            [(2, 0), (1, 1), (2, 1), (1, 2)],
+            [(0, 1), (1, 1), (1, 2), (2, 2)],
+            [(2, 0), (1, 1), (2, 1), (1, 2)],
+        ],
+        Tetromino::J => [
+            [(0, 1), (1, 1), (2, 1), (2, 2)],
+            [(1, 0), (1, 1), (0, 2), (1, 2)],
+            [(0, 1), (0, 2), (1, 2), (2, 2)],
+            [(1, 0), (2, 0), (1, 1), (1, 2)],
+        ],
+        Tetromino::L => [
+            [(0, 1), (1, 1), (2, 1), (0, 2)],
+            [(0, 0), (1, 0), (1, 1), (1, 2)],
+            [(2, 1), (0, 2), (1, 2), (2, 2)],
+            [(1, 0), (1, 1), (1, 2), (2, 2)],
+        ],
+    }
+}
+
+/// Arika Rotation System, as in the classic TGM games. Pieces spawn flat side up and stay as low as possible in their
+/// box when rotating. Obstructed rotations are kicked one cell to the right, then one to the left, except for the I
+/// piece, and except for the J, L and T pieces when the first obstructed cell (in reading order) is in the center
+/// column of their box.
+///
+/// ```
+/// use atris::algebra::Vector;
+/// use atris::piece::shape::{Shape, Tetromino};
+/// use atris::rotationsystem::RotationSystem;
+/// use atris::rotationsystems::ArikaRotationSystem;
+///
+/// let ars = ArikaRotationSystem::new();
+/// let t = ars.orientations(&Tetromino::T.shape());
+/// assert_eq!(t[0].positions(), Shape::from_iter([Vector(-1, 0), Vector(0, 0), Vector(1, 0), Vector(0, -1)]).positions());
+/// assert_eq!(t[2].positions(), Shape::from_iter([Vector(0, 0), Vector(-1, -1), Vector(0, -1), Vector(1, -1)]).positions());
+/// assert_eq!(ars.kicks(&Tetromino::T.shape(), 0, 3), vec!(Vector(0, 0), Vector(1, 0), Vector(-1, 0)));
+/// assert_eq!(ars.kicks(&Tetromino::I.shape(), 0, 3), vec!(Vector(0, 0)));
+/// assert!(ars.kick_allowed(&Tetromino::T.shape(), 0, 3, &[Vector(-1, 0), Vector(0, -1)]));
+/// assert!(!ars.kick_allowed(&Tetromino::T.shape(), 0, 3, &[Vector(0, 1), Vector(-1, 0)]));
+/// ```
+#[derive(Debug, Default)]
+pub struct ArikaRotationSystem {
+    kick_tables: KickTables,
+}
+
+impl ArikaRotationSystem {
+    /// Creates a new `ArikaRotationSystem` object without overridden kicks.
+    pub fn new() -> Self {
+        Self { kick_tables: KickTables::new() }
+    }
+
+    /// Returns the shape of `tetromino` in the rotation state `rotation` (counter-clockwise quarter turns), relative to
+    /// the center of its box.
+    fn box_shape(tetromino: Tetromino, rotation: i32) -> Shape {
+        let state = (4 - rotation.rem_euclid(4)) as usize % 4;
+        box_cells(tetromino)[state].iter().map(|&(col, row)| Vector(col - 1, 1 - row)).collect()
+    }
+}
+
+impl RotationSystem for ArikaRotationSystem {
+    fn id(&self) -> &'static str { "ARS" }
+
+    fn spawn_shape(&self, shape: &Shape) -> Shape {
+        match Tetromino::of(shape) {
+            Some(tetromino) => Self::box_shape(tetromino, 0),
+            None => shape.clone(),
+        }
+    }
+
+    fn orientations(&self, shape: &Shape) -> Vec<Shape> {
+        match Tetromino::of(shape) {
+            Some(tetromino) => (0..4).map(|rotation| Self::box_shape(tetromino, rotation)).collect(),
+            None => {
+                let pivot = self.pivot(shape);
+                (0..4).map(|angle| shape.rotated_around(angle, pivot)).collect()
+            },
+        }
+    }
+
+    fn kick_tables(&self) -> &KickTables { &self.kick_tables }
+
+    fn kick_tables_mut(&mut self) -> &mut KickTables { &mut self.kick_tables }
+
+    fn default_kicks(&self, shape: &Shape, _from: i32, _to: i32) -> Vec<IVector> {
+        match Tetromino::of(shape) {
+            Some(Tetromino::I) | Some(Tetromino::O) => vec!(Vector(0, 0)),
+            _ => vec!(Vector(0, 0), Vector(1, 0), Vector(-1, 0)),
+        }
+    }
+
+    fn kick_allowed(&self, shape: &Shape, _from: i32, _to: i32, obstructed: &[IVector]) -> bool {
+        match Tetromino::of(shape) {
+            Some(Tetromino::J) | Some(Tetromino::L) | Some(Tetromino::T) => {
+                // Reading order: top to bottom, left to right
+                let first = obstructed.iter().min_by_key(|p| (-p.1, p.0));
+                !matches!(first, Some(p) if p.0 == 0)
+            },
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,8 @@
+pub mod srs;
+pub mod ars;
+pub mod nrs;
+// Add new `RotationSystem` structs modules here
+
+pub use srs::SuperRotationSystem;
+pub use ars::ArikaRotationSystem;
+pub use nrs::NintendoRotationSystem;
\ No newline at end of file
@@ -0,0 +1,93 @@
+use crate::algebra::{Vector, IVector};
+use crate::piece::shape::{Shape, Tetromino};
+use crate::rotationsystem::{RotationSystem, KickTables};
+
+/// Nintendo Rotation System, as in NES Tetris. Pieces spawn flat side up, the J, L and T pieces rotate around their
+/// center block, the I, S and Z pieces only have two rotation states and the O piece only one. Rotations are never
+/// kicked, unless their kicks are overridden.
+///
+/// ```
+/// use atris::algebra::Vector;
+/// use atris::piece::shape::{Shape, Tetromino};
+/// use atris::rotationsystem::RotationSystem;
+/// use atris::rotationsystems::NintendoRotationSystem;
+///
+/// let nrs = NintendoRotationSystem::new();
+/// let s = nrs.orientations(&Tetromino::S.shape());
+/// assert_eq!(s[0].positions(), s[2].positions());
+/// assert_eq!(s[1].positions(), s[3].positions());
+/// assert_eq!(s[1].positions(), Shape::from_iter([Vector(0, 1), Vector(0, 0), Vector(1, 0), Vector(1, -1)]).positions());
+/// assert_eq!(nrs.kicks(&Tetromino::T.shape(), 0, 1), vec!(Vector(0, 0)));
+/// ```
+#[derive(Debug, Default)]
+pub struct NintendoRotationSystem {
+    kick_tables: KickTables,
+}
+
+impl NintendoRotationSystem {
+    /// Creates a new `NintendoRotationSystem` object without overridden kicks.
+    pub fn new() -> Self {
+        Self { kick_tables: KickTables::new() }
+    }
+
+    /// Returns the spawn and the vertical states of the pieces with only two rotation states.
+    fn two_states(tetromino: Tetromino) -> Option<[[IVector; 4]; 2]> {
+        match tetromino {
+            Tetromino::I => Some([
+                [Vector(-2, 0), Vector(-1, 0), Vector(0, 0), Vector(1, 0)],
+                [Vector(0, 2), Vector(0, 1), Vector(0, 0), Vector(0, -1)],
+            ]),
+            Tetromino::S => Some([
+                [Vector(0, 0), Vector(1, 0), Vector(-1, -1), Vector(0, -1)],
+                [Vector(0, 1), Vector(0, 0), Vector(1, 0), Vector(1, -1)],
+            ]),
+            Tetromino::Z => Some([
+                [Vector(-1, 0), Vector(0, 0), Vector(0, -1), Vector(1, -1)],
+                [Vector(1, 1), Vector(0, 0), Vector(1, 0), Vector(0, -1)],
+            ]),
+            _ => None,
+        }
+    }
+}
+
+impl RotationSystem for NintendoRotationSystem {
+    fn id(&self) -> &'static str { "NRS" }
+
+    fn spawn_shape(&self, shape: &Shape) -> Shape {
+        let positions = match Tetromino::of(shape) {
+            Some(Tetromino::O) => [Vector(-1, 0), Vector(0, 0), Vector(-1, -1), Vector(0, -1)],
+            Some(Tetromino::T) => [Vector(-1, 0), Vector(0, 0), Vector(1, 0), Vector(0, -1)],
+            Some(Tetromino::J) => [Vector(-1, 0), Vector(0, 0), Vector(1, 0), Vector(1, -1)],
+            Some(Tetromino::L) => [Vector(-1, 0), Vector(0, 0), Vector(1, 0), Vector(-1, -1)],
+            Some(tetromino) => Self::two_states(tetromino).unwrap()[0],
+            None => return shape.clone(),
+        };
+        Shape::from_iter(positions)
+    }
+
+    fn orientations(&self, shape: &Shape) -> Vec<Shape> {
+        let spawn_shape = self.spawn_shape(shape);
+        match Tetromino::of(shape) {
+            Some(Tetromino::O) => vec!(spawn_shape; 4),
+            Some(Tetromino::T) | Some(Tetromino::J) | Some(Tetromino::L) => {
+                (0..4).map(|angle| spawn_shape.rotated(angle)).collect()
+            },
+            Some(tetromino) => {
+                let states = Self::two_states(tetromino).unwrap();
+                (0..4).map(|angle| Shape::from_iter(states[angle % 2])).collect()
+            },
+            None => {
+                let pivot = self.pivot(&spawn_shape);
+                (0..4).map(|angle| spawn_shape.rotated_around(angle, pivot)).collect()
+            },
+        }
+    }
+
+    fn kick_tables(&self) -> &KickTables { &self.kick_tables }
+
+    fn kick_tables_mut(&mut self) -> &mut KickTables { &mut self.kick_tables }
+
+    fn default_kicks(&self, _shape: &Shape, _from: i32, _to: i32) -> Vec<IVector> {
+        vec!(Vector(0, 0))
+    }
+}
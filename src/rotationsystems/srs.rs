@@ -0,0 +1,108 @@
+use crate::algebra::{Vector, IVector, FVector};
+use crate::piece::shape::{Shape, Tetromino};
+use crate::rotationsystem::{RotationSystem, KickTables, center_pivot, flat_orientation};
+
+/// Offsets of the J, L, S, T and Z pieces for the states 0, R, 2 and L (clockwise order).
+const JLSTZ_OFFSETS: [[IVector; 5]; 4] = [
+    [Vector(0, 0), Vector(0, 0), Vector(0, 0), Vector(0, 0), Vector(0, 0)],
+    [Vector(0, 0), Vector(1, 0), Vector(1, -1), Vector(0, 2), Vector(1, 2)],
+    [Vector(0, 0), Vector(0, 0), Vector(0, 0), Vector(0, 0), Vector(0, 0)],
+    [Vector(0, 0), Vector(-1, 0), Vector(-1, -1), Vector(0, 2), Vector(-1, 2)],
+];
+
+/// Offsets of the I piece for the states 0, R, 2 and L (clockwise order). These are the guideline ones made relative
+/// to the first one, as the I piece rotates around the center of its 4x4 box instead of around one of its blocks.
+const I_OFFSETS: [[IVector; 5]; 4] = [
+    [Vector(0, 0), Vector(-1, 0), Vector(2, 0), Vector(-1, 0), Vector(2, 0)],
+    [Vector(0, 0), Vector(1, 0), Vector(1, 0), Vector(1, 1), Vector(1, -2)],
+    [Vector(0, 0), Vector(2, 0), Vector(-1, 0), Vector(2, -1), Vector(-1, -1)],
+    [Vector(0, 0), Vector(0, 0), Vector(0, 0), Vector(0, -2), Vector(0, 1)],
+];
+
+/// Guideline Super Rotation System. Pieces spawn flat side down and rotate around their center, and each rotation
+/// tests 5 positions given by the guideline kick tables. 180 degree rotations are not kicked.
+///
+/// Shapes other than tetrominoes spawn in their flattest orientation, and their kicks are generated from their size
+/// and symmetry with `KickTable::generate`, once per shape. The kicks of any shape can be overridden with
+/// `RotationSystem::set_kick_table`.
+///
+/// ```
+/// use atris::algebra::Vector;
+/// use atris::piece::shape::Tetromino;
+/// use atris::rotationsystem::RotationSystem;
+/// use atris::rotationsystems::SuperRotationSystem;
+///
+/// let srs = SuperRotationSystem::new();
+/// let t = Tetromino::T.shape();
+/// // 0->R
+/// assert_eq!(srs.kicks(&t, 0, 3), vec!(Vector(0, 0), Vector(-1, 0), Vector(-1, 1), Vector(0, -2), Vector(-1, -2)));
+/// // L->0
+/// assert_eq!(srs.kicks(&t, 1, 0), vec!(Vector(0, 0), Vector(-1, 0), Vector(-1, -1), Vector(0, 2), Vector(-1, 2)));
+/// let i = Tetromino::I.shape();
+/// // 0->R and R->2
+/// assert_eq!(srs.kicks(&i, 0, 3), vec!(Vector(0, 0), Vector(-2, 0), Vector(1, 0), Vector(-2, -1), Vector(1, 2)));
+/// assert_eq!(srs.kicks(&i, 3, 2), vec!(Vector(0, 0), Vector(-1, 0), Vector(2, 0), Vector(-1, 2), Vector(2, -1)));
+/// // The O piece never moves when rotated
+/// let o = srs.orientations(&Tetromino::O.shape());
+/// assert!(o.iter().all(|shape| shape.positions() == o[0].positions()));
+/// ```
+#[derive(Debug, Default)]
+pub struct SuperRotationSystem {
+    kick_tables: KickTables,
+}
+
+impl SuperRotationSystem {
+    /// Creates a new `SuperRotationSystem` object without overridden kicks.
+    pub fn new() -> Self {
+        Self { kick_tables: KickTables::new() }
+    }
+
+    /// Converts a rotation state (counter-clockwise quarter turns) to an index in the offset tables (clockwise order).
+    fn table_index(rotation: i32) -> usize {
+        (4 - rotation.rem_euclid(4)) as usize % 4
+    }
+
+    fn offsets(tetromino: Tetromino) -> Option<&'static [[IVector; 5]; 4]> {
+        match tetromino {
+            Tetromino::I => Some(&I_OFFSETS),
+            Tetromino::O => None,
+            _ => Some(&JLSTZ_OFFSETS),
+        }
+    }
+}
+
+impl RotationSystem for SuperRotationSystem {
+    fn id(&self) -> &'static str { "SRS" }
+
+    fn spawn_shape(&self, shape: &Shape) -> Shape {
+        match Tetromino::of(shape) {
+            Some(tetromino) => tetromino.shape(),
+            None => flat_orientation(shape),
+        }
+    }
+
+    fn pivot(&self, spawn_shape: &Shape) -> FVector {
+        match Tetromino::of(spawn_shape) {
+            Some(Tetromino::I) => Vector(0.5, -0.5),
+            Some(Tetromino::O) => Vector(0.5, 0.5),
+            Some(_) => Vector(0.0, 0.0),
+            None => center_pivot(spawn_shape),
+        }
+    }
+
+    fn kick_tables(&self) -> &KickTables { &self.kick_tables }
+
+    fn kick_tables_mut(&mut self) -> &mut KickTables { &mut self.kick_tables }
+
+    fn default_kicks(&self, shape: &Shape, from: i32, to: i32) -> Vec<IVector> {
+        let offsets = match Tetromino::of(shape) {
+            Some(tetromino) => match Self::offsets(tetromino) {
+                Some(offsets) if (to - from).rem_euclid(2) == 1 => offsets,
+                _ => return vec!(Vector(0, 0)),
+            },
+            None => return self.kick_tables.generated(&self.spawn_shape(shape), from, to),
+        };
+        let (from, to) = (Self::table_index(from), Self::table_index(to));
+        (0..5).map(|i| offsets[from][i] - offsets[to][i]).collect()
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt::Debug;
+
+use crate::grid::Spin;
+
+/// What happened when a `Figure` locked, for a `ScoringRule` to score it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lock {
+    /// Number of rows the `Figure` filled, that will be cleared.
+    pub lines: usize,
+    /// The spin the `Figure` did before locking.
+    pub spin: Spin,
+    /// Whether the grid is left empty once the rows are cleared.
+    pub perfect_clear: bool,
+    /// Level of the grid when the `Figure` locked.
+    pub level: u32,
+}
+
+impl Lock {
+    /// Checks whether the lock is a difficult clear, which makes the next one a back-to-back: clearing
+    /// `difficult_lines` rows or more at once, or clearing rows with a spin.
+    ///
+    /// ```
+    /// use atris::grid::Spin;
+    /// use atris::scoringrule::Lock;
+    ///
+    /// assert!(Lock { lines: 4, ..Default::default() }.is_difficult(4));
+    /// assert!(!Lock { lines: 4, ..Default::default() }.is_difficult(5));
+    /// assert!(Lock { lines: 1, spin: Spin::Mini, ..Default::default() }.is_difficult(5));
+    /// assert!(!Lock { lines: 0, spin: Spin::Full, ..Default::default() }.is_difficult(4));
+    /// ```
+    pub fn is_difficult(&self, difficult_lines: usize) -> bool {
+        self.lines > 0 && (self.lines >= difficult_lines || self.spin != Spin::None)
+    }
+}
+
+/// This `trait` represents how points are scored. Scoring rules can keep a state (combos, back-to-backs...), so each
+/// grid uses its own.
+pub trait ScoringRule: Send + Sync + Debug {
+    /// Returns the identifier (an unique name) of the `ScoringRule` object. The value returned must be constant all the
+    /// time.
+    fn id(&self) -> &'static str;
+
+    /// Returns the points scored by a `Figure` locking, and updates the state of the rule.
+    fn on_lock(&mut self, lock: &Lock) -> u64;
+
+    /// Returns the points scored by a `Figure` falling `cells` cells because it was soft dropped, or hard dropped if
+    /// `hard`. No points by default.
+    fn on_drop(&mut self, _cells: u32, _hard: bool, _level: u32) -> u64 { 0 }
+
+    /// Returns the minimum number of rows of a difficult clear (see `Lock::is_difficult`), so that the attack of the
+    /// grid chains back-to-backs like the rule does. 4 by default.
+    fn difficult_lines(&self) -> usize { 4 }
+
+    /// Returns a new `ScoringRule` with the same settings, in its initial state.
+    fn restarted(&self) -> Box<dyn ScoringRule>;
+
+    /// Returns the state of the rule (combos, back-to-backs...), to save it. Rules without a state return an empty one.
+    fn state(&self) -> Vec<u64> { Vec::new() }
+
+    /// Restores a state returned by `state`. Returns whether it was a valid state for the rule, leaving the rule
+    /// unchanged if it wasn't.
+    fn set_state(&mut self, state: &[u64]) -> bool { state.is_empty() }
+}
+
+/// A `ScoringRule` is serialized with its id and its state.
+#[cfg(feature = "serde")]
+impl serde::Serialize for dyn ScoringRule {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut rule = serializer.serialize_struct("ScoringRule", 2)?;
+        rule.serialize_field("id", self.id())?;
+        rule.serialize_field("state", &self.state())?;
+        rule.end()
+    }
+}
+
+/// Returns the points given by `table` for clearing `lines` rows at once. Past the end of the table, points keep growing
+/// by the difference between its last two values, so rules made for 4 lines work with bigger pieces.
+///
+/// ```
+/// use atris::scoringrule::table_points;
+///
+/// let table = [0, 100, 300, 500, 800];
+/// assert_eq!(table_points(&table, 2), 300);
+/// assert_eq!(table_points(&table, 5), 1100);
+/// assert_eq!(table_points(&table, 6), 1400);
+/// assert_eq!(table_points(&[], 3), 0);
+/// ```
+pub fn table_points(table: &[u64], lines: usize) -> u64 {
+    match (table.get(lines), table) {
+        (Some(&points), _) => points,
+        (None, &[]) => 0,
+        (None, &[only]) => only,
+        (None, &[.., second_last, last]) => last + (lines + 1 - table.len()) as u64 * last.saturating_sub(second_last),
+    }
+}
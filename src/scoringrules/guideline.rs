@@ -0,0 +1,134 @@
+use crate::grid::Spin;
+use crate::scoringrule::{table_points, Lock, ScoringRule};
+
+/// Scoring of the Tetris guideline: 100, 300, 500 and 800 points for 1 to 4 lines, multiplied by the level, and more
+/// for spins. Difficult clears (4 lines or more, or spins clearing lines) in a row are worth 1.5 times more
+/// (back-to-back), consecutive clears add 50 points per combo and level, and perfect clears add a bonus. Soft drops
+/// score 1 point per cell, hard drops 2.
+///
+/// ```
+/// use atris::grid::Spin;
+/// use atris::scoringrule::{Lock, ScoringRule};
+/// use atris::scoringrules::GuidelineScoring;
+///
+/// let mut guideline = GuidelineScoring::new();
+/// let lock = |lines| Lock { lines, level: 1, ..Default::default() };
+/// assert_eq!(guideline.on_lock(&lock(4)), 800);
+/// assert_eq!(guideline.on_lock(&lock(4)), 1200 + 50); // Back-to-back, combo 1
+/// assert_eq!(guideline.on_lock(&lock(1)), 100 + 100); // Combo 2, the back-to-back is lost
+/// assert_eq!(guideline.on_lock(&lock(0)), 0); // The combo is lost
+/// assert_eq!(guideline.on_lock(&Lock { perfect_clear: true, ..lock(2) }), 300 + 1200);
+/// assert_eq!(guideline.on_drop(3, true, 1), 6);
+/// let t_spin = |lines| Lock { spin: Spin::Full, ..lock(lines) };
+/// assert_eq!(guideline.on_lock(&t_spin(0)), 400);
+/// assert_eq!(guideline.on_lock(&t_spin(2)), 1200);
+/// assert_eq!(guideline.on_lock(&t_spin(2)), 1800 + 50); // Back-to-back, combo 1
+/// // Only back-to-back perfect clears of 4 lines or more get the back-to-back bonus
+/// assert_eq!(guideline.on_lock(&Lock { perfect_clear: true, ..t_spin(2) }), 1800 + 100 + 1200);
+/// assert_eq!(guideline.on_lock(&Lock { perfect_clear: true, ..lock(4) }), 1200 + 150 + 3200);
+/// ```
+#[derive(Clone, Debug)]
+pub struct GuidelineScoring {
+    line_points: Vec<u64>,
+    /// Points of full spins, by lines cleared.
+    spin_points: Vec<u64>,
+    /// Points of mini spins, by lines cleared.
+    mini_spin_points: Vec<u64>,
+    perfect_clear_points: Vec<u64>,
+    /// Perfect clear bonus of a back-to-back clear of `difficult_lines` lines or more.
+    back_to_back_perfect_clear_points: u64,
+    /// Minimum number of lines of a difficult clear.
+    difficult_lines: usize,
+    combo: Option<u32>,
+    back_to_back: bool,
+}
+
+impl GuidelineScoring {
+    pub fn new() -> Self {
+        Self {
+            line_points: vec!(0, 100, 300, 500, 800),
+            spin_points: vec!(400, 800, 1200, 1600),
+            mini_spin_points: vec!(100, 200, 400),
+            perfect_clear_points: vec!(0, 800, 1200, 1800, 2000),
+            back_to_back_perfect_clear_points: 3200,
+            difficult_lines: 4,
+            combo: None,
+            back_to_back: false,
+        }
+    }
+
+    /// Sets the minimum number of lines of a difficult clear (4 by default), for bigger pieces.
+    pub fn set_difficult_lines(&mut self, difficult_lines: usize) {
+        self.difficult_lines = difficult_lines;
+    }
+
+    /// Returns the number of consecutive clears after the first one, if the last `Figure` cleared lines.
+    pub fn combo(&self) -> Option<u32> { self.combo }
+
+    /// Checks whether the next difficult clear is back-to-back.
+    pub fn back_to_back(&self) -> bool { self.back_to_back }
+}
+
+impl Default for GuidelineScoring {
+    fn default() -> Self { Self::new() }
+}
+
+impl ScoringRule for GuidelineScoring {
+    fn id(&self) -> &'static str { "guideline" }
+
+    fn on_lock(&mut self, lock: &Lock) -> u64 {
+        let level = lock.level.max(1) as u64;
+        let table = match lock.spin {
+            Spin::None => &self.line_points,
+            Spin::Mini => &self.mini_spin_points,
+            Spin::Full => &self.spin_points,
+        };
+        let mut points = table_points(table, lock.lines) * level;
+        if lock.lines == 0 {
+            self.combo = None;
+            return points;
+        }
+        let difficult = lock.is_difficult(self.difficult_lines);
+        let back_to_back = difficult && self.back_to_back;
+        if back_to_back {
+            points = points * 3 / 2;
+        }
+        self.combo = Some(self.combo.map_or(0, |combo| combo + 1));
+        points += 50 * self.combo.unwrap_or(0) as u64 * level;
+        if lock.perfect_clear {
+            points += level * match back_to_back && lock.lines >= self.difficult_lines {
+                true => self.back_to_back_perfect_clear_points,
+                false => table_points(&self.perfect_clear_points, lock.lines),
+            };
+        }
+        self.back_to_back = difficult;
+        points
+    }
+
+    fn difficult_lines(&self) -> usize { self.difficult_lines }
+
+    fn on_drop(&mut self, cells: u32, hard: bool, _level: u32) -> u64 {
+        cells as u64 * if hard { 2 } else { 1 }
+    }
+
+    fn restarted(&self) -> Box<dyn ScoringRule> {
+        Box::new(Self { combo: None, back_to_back: false, ..self.clone() })
+    }
+
+    /// The state is the combo plus one (0 without combo), and 1 if the next difficult clear is back-to-back.
+    fn state(&self) -> Vec<u64> {
+        vec!(self.combo.map_or(0, |combo| combo as u64 + 1), self.back_to_back as u64)
+    }
+
+    fn set_state(&mut self, state: &[u64]) -> bool {
+        let &[combo, back_to_back @ (0 | 1)] = state else {
+            return false;
+        };
+        let Ok(combo) = combo.checked_sub(1).map(u32::try_from).transpose() else {
+            return false;
+        };
+        self.combo = combo;
+        self.back_to_back = back_to_back == 1;
+        true
+    }
+}
@@ -0,0 +1,6 @@
+pub mod guideline;
+pub mod nes;
+// Add new `ScoringRule` structs modules here
+
+pub use guideline::GuidelineScoring;
+pub use nes::NesScoring;
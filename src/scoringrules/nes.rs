@@ -0,0 +1,44 @@
+use crate::scoringrule::{table_points, Lock, ScoringRule};
+
+/// Scoring of NES Tetris: 40, 100, 300 and 1200 points for 1 to 4 lines, multiplied by the level plus one, and one
+/// point per cell soft dropped. There are no combos nor back-to-backs.
+///
+/// ```
+/// use atris::scoringrule::{Lock, ScoringRule};
+/// use atris::scoringrules::NesScoring;
+///
+/// let mut nes = NesScoring::new();
+/// assert_eq!(nes.on_lock(&Lock { lines: 4, level: 0, ..Default::default() }), 1200);
+/// assert_eq!(nes.on_lock(&Lock { lines: 2, level: 9, ..Default::default() }), 1000);
+/// assert_eq!(nes.on_drop(5, false, 9), 5);
+/// ```
+#[derive(Clone, Debug)]
+pub struct NesScoring {
+    line_points: Vec<u64>,
+}
+
+impl NesScoring {
+    pub fn new() -> Self {
+        Self {
+            line_points: vec!(0, 40, 100, 300, 1200),
+        }
+    }
+}
+
+impl Default for NesScoring {
+    fn default() -> Self { Self::new() }
+}
+
+impl ScoringRule for NesScoring {
+    fn id(&self) -> &'static str { "nes" }
+
+    fn on_lock(&mut self, lock: &Lock) -> u64 {
+        table_points(&self.line_points, lock.lines) * (lock.level as u64 + 1)
+    }
+
+    fn on_drop(&mut self, cells: u32, hard: bool, _level: u32) -> u64 {
+        if hard { 0 } else { cells as u64 }
+    }
+
+    fn restarted(&self) -> Box<dyn ScoringRule> { Box::new(self.clone()) }
+}
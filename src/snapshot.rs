@@ -0,0 +1,60 @@
+use serde::{de::{DeserializeOwned, Error}, Deserializer};
+
+use crate::blocktypes::BlockTypes;
+
+/// This `trait` represents the values that borrow `BlockType`s (blocks, pieces, figures, grids...), which can't be
+/// deserialized on their own: their block types are serialized by their `id`, and resolved through a `BlockTypes` map
+/// when loading them.
+///
+/// ```
+/// use atris::algebra::Vector;
+/// use atris::blocktypes::{BlockTypes, standard::StandardType};
+/// use atris::grid::Grid;
+/// use atris::block::Block;
+/// use atris::snapshot::DeserializeWith;
+///
+/// let mut grid = Grid::new(Vector(4, 3));
+/// grid[Vector(1, 0)] = Some(Block::new(&StandardType{}));
+/// let json = serde_json::to_string(&grid).unwrap();
+/// let block_types = BlockTypes::base_types();
+/// let loaded = Grid::deserialize_with(&mut serde_json::Deserializer::from_str(&json), &block_types).unwrap();
+/// assert_eq!(loaded[Vector(1, 0)].as_ref().unwrap().block_type.id(), "Standard");
+/// assert!(loaded[Vector(2, 0)].is_none());
+/// assert!(Grid::deserialize_with(&mut serde_json::Deserializer::from_str(&json), &BlockTypes::new()).is_err());
+/// ```
+pub trait DeserializeWith<'bt>: Sized {
+    /// Deserializes a value, taking its block types from `block_types`. Unknown block types are an error.
+    fn deserialize_with<'de, D: Deserializer<'de>>(deserializer: D, block_types: &'bt BlockTypes)
+        -> Result<Self, D::Error>;
+}
+
+/// The serialized form of a value borrowing `BlockType`s, before its block types are resolved.
+pub(crate) trait Unresolved<'bt>: DeserializeOwned {
+    type Resolved;
+
+    /// Resolves the block types of the value through `block_types`, and checks it's consistent.
+    fn resolve(self, block_types: &'bt BlockTypes) -> Result<Self::Resolved, String>;
+}
+
+impl<'bt, U: Unresolved<'bt>> Unresolved<'bt> for Option<U> {
+    type Resolved = Option<U::Resolved>;
+
+    fn resolve(self, block_types: &'bt BlockTypes) -> Result<Self::Resolved, String> {
+        self.map(|value| value.resolve(block_types)).transpose()
+    }
+}
+
+impl<'bt, U: Unresolved<'bt>> Unresolved<'bt> for Vec<U> {
+    type Resolved = Vec<U::Resolved>;
+
+    fn resolve(self, block_types: &'bt BlockTypes) -> Result<Self::Resolved, String> {
+        self.into_iter().map(|value| value.resolve(block_types)).collect()
+    }
+}
+
+/// Deserializes the serialized form `U` of a value, and resolves its block types.
+pub(crate) fn deserialize_resolved<'de, 'bt, U, D>(deserializer: D, block_types: &'bt BlockTypes)
+        -> Result<U::Resolved, D::Error>
+where U: Unresolved<'bt>, D: Deserializer<'de> {
+    U::deserialize(deserializer)?.resolve(block_types).map_err(D::Error::custom)
+}
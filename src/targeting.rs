@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+/// How a grid picks the opponents its garbage is sent to.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TargetingStrategy {
+    /// A random opponent, picked again on every attack.
+    #[default]
+    Random,
+    /// The opponent that was targeted the fewest times so far, so the garbage is spread evenly.
+    Even,
+    /// The opponent with the highest stack, the closest one to being knocked out.
+    KOs,
+    /// All the opponents targeting the grid, or a random one if none is.
+    Attackers,
+    /// The given opponent, or a random one once it's knocked out.
+    Manual(usize),
+}
+
+/// A grid taking part in the targeting of a session.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Contender {
+    pub strategy: TargetingStrategy,
+    pub alive: bool,
+    /// Team of the grid, if any. Grids of the same team don't attack each other.
+    pub team: Option<usize>,
+    /// Height of the stack of the grid, in rows.
+    pub height: usize,
+    /// Opponents targeted by the last attack of the grid.
+    pub targets: Vec<usize>,
+    /// Number of attacks that targeted the grid.
+    pub times_targeted: u64,
+    /// The last grid that sent garbage to this one, credited with the KO if it's knocked out.
+    pub last_attacker: Option<usize>,
+    /// Number of opponents knocked out by the grid.
+    pub kos: u32,
+    /// Badge points earned by the grid: 1 per KO plus the badge points of the opponent knocked out.
+    pub badges: u32,
+}
+
+impl Contender {
+    /// Checks whether the grid is in the same team as `other`.
+    pub fn allied_with(&self, other: &Contender) -> bool {
+        self.team.is_some() && self.team == other.team
+    }
+}
+
+/// Keeps track of who targets who among the grids of a session, and of the KOs, to decide where garbage goes. Random
+/// picks come from its own generator, so they only depend on its seed and on the attacks made.
+///
+/// ```
+/// use atris::targeting::{Targeting, TargetingStrategy};
+///
+/// let mut targeting = Targeting::with_seed(0);
+/// for grid_id in 1..=3 {
+///     targeting.add(grid_id);
+/// }
+/// targeting.set_height(3, 12);
+/// targeting.set_strategy(1, TargetingStrategy::KOs);
+/// assert_eq!(targeting.attack(1, 4), vec!((3, 4)));
+/// targeting.set_strategy(3, TargetingStrategy::Attackers);
+/// assert_eq!(targeting.attack(3, 2), vec!((1, 2)));
+/// assert_eq!(targeting.knock_out(3), Some(1));
+/// assert_eq!(targeting.get(1).unwrap().kos, 1);
+/// assert_eq!(targeting.attack(1, 4), vec!((2, 4))); // The only opponent left
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Targeting {
+    contenders: BTreeMap<usize, Contender>,
+    rng: ChaCha12Rng,
+    /// Whether badges multiply the attack of the grids.
+    badges: bool,
+    /// Whether the garbage sent to a grid is split across its teammates alive.
+    split: bool,
+}
+
+impl Targeting {
+    /// Creates a targeting with a random seed.
+    pub fn new() -> Self {
+        Self::with_seed(rand::thread_rng().gen())
+    }
+
+    /// Creates a targeting whose random picks are derived from `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            contenders: BTreeMap::new(),
+            rng: ChaCha12Rng::seed_from_u64(seed),
+            badges: false,
+            split: false,
+        }
+    }
+
+    /// Checks whether badges multiply the attack of the grids.
+    pub fn badges(&self) -> bool { self.badges }
+
+    /// Sets whether badges multiply the attack of the grids, as given by `badge_bonus`.
+    pub fn set_badges(&mut self, badges: bool) {
+        self.badges = badges;
+    }
+
+    /// Checks whether the garbage sent to a grid is split across its teammates alive.
+    pub fn split(&self) -> bool { self.split }
+
+    /// Sets whether the garbage sent to a grid is split across its teammates alive, instead of all going to it.
+    pub fn set_split(&mut self, split: bool) {
+        self.split = split;
+    }
+
+    /// Returns the grids taking part, alive or not.
+    pub fn contenders(&self) -> &BTreeMap<usize, Contender> { &self.contenders }
+
+    pub fn get(&self, grid_id: usize) -> Option<&Contender> { self.contenders.get(&grid_id) }
+
+    /// Returns the ids of the grids still alive.
+    pub fn alive(&self) -> impl Iterator<Item = usize> + '_ {
+        self.contenders.iter().filter(|(_, contender)| contender.alive).map(|(&id, _)| id)
+    }
+
+    /// Returns the number of sides still alive: teams with a grid alive, and grids alive without a team.
+    pub fn sides_alive(&self) -> usize {
+        let mut teams: Vec<usize> = self.contenders.values().filter(|contender| contender.alive)
+            .filter_map(|contender| contender.team).collect();
+        teams.sort_unstable();
+        teams.dedup();
+        let lone = self.contenders.values().filter(|contender| contender.alive && contender.team.is_none()).count();
+        teams.len() + lone
+    }
+
+    /// Adds a grid, alive, with the `Random` strategy.
+    pub fn add(&mut self, grid_id: usize) {
+        self.contenders.insert(grid_id, Contender { alive: true, ..Default::default() });
+    }
+
+    pub fn remove(&mut self, grid_id: usize) {
+        self.contenders.remove(&grid_id);
+    }
+
+    pub fn set_strategy(&mut self, grid_id: usize, strategy: TargetingStrategy) {
+        if let Some(contender) = self.contenders.get_mut(&grid_id) {
+            contender.strategy = strategy;
+        }
+    }
+
+    /// Puts a grid in a team, or in none.
+    pub fn set_team(&mut self, grid_id: usize, team: Option<usize>) {
+        if let Some(contender) = self.contenders.get_mut(&grid_id) {
+            contender.team = team;
+        }
+    }
+
+    /// Updates the height of the stack of a grid, for the `KOs` strategy.
+    pub fn set_height(&mut self, grid_id: usize, height: usize) {
+        if let Some(contender) = self.contenders.get_mut(&grid_id) {
+            contender.height = height;
+        }
+    }
+
+    /// Picks the targets of an attack of a grid among the opponents alive (the grids of other teams), following its
+    /// strategy.
+    fn pick(&mut self, grid_id: usize) -> Vec<usize> {
+        let Some(attacker) = self.contenders.get(&grid_id) else {
+            return Vec::new();
+        };
+        let opponents: Vec<(usize, &Contender)> = self.contenders.iter()
+            .filter(|&(&id, contender)| id != grid_id && contender.alive && !contender.allied_with(attacker))
+            .map(|(&id, contender)| (id, contender))
+            .collect();
+        let rng = &mut self.rng;
+        let random = |rng: &mut ChaCha12Rng, candidates: Vec<usize>| {
+            candidates.choose(rng).copied().into_iter().collect::<Vec<usize>>()
+        };
+        let ids = opponents.iter().map(|&(id, _)| id).collect();
+        match attacker.strategy {
+            TargetingStrategy::Random => random(rng, ids),
+            TargetingStrategy::Even => {
+                let fewest = opponents.iter().map(|(_, opponent)| opponent.times_targeted).min();
+                random(rng, opponents.iter().filter(|(_, opponent)| Some(opponent.times_targeted) == fewest)
+                    .map(|&(id, _)| id).collect())
+            },
+            TargetingStrategy::KOs => {
+                let highest = opponents.iter().map(|(_, opponent)| opponent.height).max();
+                random(rng, opponents.iter().filter(|(_, opponent)| Some(opponent.height) == highest)
+                    .map(|&(id, _)| id).collect())
+            },
+            TargetingStrategy::Attackers => {
+                let attackers: Vec<usize> = opponents.iter()
+                    .filter(|(_, opponent)| opponent.targets.contains(&grid_id))
+                    .map(|&(id, _)| id).collect();
+                if attackers.is_empty() { random(rng, ids) } else { attackers }
+            },
+            TargetingStrategy::Manual(target) => {
+                if opponents.iter().any(|&(id, _)| id == target) { vec!(target) } else { random(rng, ids) }
+            },
+        }
+    }
+
+    /// Sends an attack of `lines` lines from a grid: picks its targets, and returns how many lines each grid gets, after
+    /// the badge bonus. With the `Attackers` strategy, every attacker gets the whole attack. When splitting, the lines
+    /// sent to a target are shared with its teammates alive, the target getting the remainder.
+    pub fn attack(&mut self, grid_id: usize, lines: u32) -> Vec<(usize, u32)> {
+        let targets = self.pick(grid_id);
+        let Some(attacker) = self.contenders.get_mut(&grid_id) else {
+            return Vec::new();
+        };
+        attacker.targets = targets.clone();
+        let lines = if self.badges { lines * (100 + badge_bonus(attacker.badges)) / 100 } else { lines };
+        let mut received: BTreeMap<usize, u32> = BTreeMap::new();
+        for &target in targets.iter() {
+            let mut receivers = vec!(target);
+            if let Some(team) = self.contenders[&target].team.filter(|_| self.split) {
+                receivers.extend(self.contenders.iter()
+                    .filter(|&(&id, contender)| id != target && contender.alive && contender.team == Some(team))
+                    .map(|(&id, _)| id));
+            }
+            let n = receivers.len() as u32;
+            for (i, receiver) in receivers.into_iter().enumerate() {
+                *received.entry(receiver).or_default() += lines / n + u32::from((i as u32) < lines % n);
+            }
+        }
+        received.retain(|_, &mut lines| lines > 0);
+        for &target in received.keys() {
+            if let Some(contender) = self.contenders.get_mut(&target) {
+                contender.times_targeted += 1;
+                contender.last_attacker = Some(grid_id);
+            }
+        }
+        received.into_iter().collect()
+    }
+
+    /// Knocks out a grid, crediting the last grid that attacked it if it's still alive. Returns the grid credited.
+    pub fn knock_out(&mut self, grid_id: usize) -> Option<usize> {
+        let contender = self.contenders.get_mut(&grid_id).filter(|contender| contender.alive)?;
+        contender.alive = false;
+        contender.targets.clear();
+        let badges = contender.badges;
+        let credited = contender.last_attacker?;
+        let winner = self.contenders.get_mut(&credited).filter(|contender| contender.alive)?;
+        winner.kos += 1;
+        winner.badges += 1 + badges;
+        Some(credited)
+    }
+}
+
+impl Default for Targeting {
+    fn default() -> Self { Self::new() }
+}
+
+/// Returns the percentage added to the attack of a grid with the given badge points: 25% from 2 points, 50% from 6,
+/// 75% from 14 and 100% from 30.
+///
+/// ```
+/// use atris::targeting::badge_bonus;
+///
+/// assert_eq!(badge_bonus(1), 0);
+/// assert_eq!(badge_bonus(6), 50);
+/// assert_eq!(badge_bonus(100), 100);
+/// ```
+pub fn badge_bonus(badges: u32) -> u32 {
+    match badges {
+        0..=1 => 0,
+        2..=5 => 25,
+        6..=13 => 50,
+        14..=29 => 75,
+        _ => 100,
+    }
+}
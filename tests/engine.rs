@@ -0,0 +1,187 @@
+use atris::algebra::Vector;
+use atris::block::Block;
+use atris::blocktypes::rock::RockType;
+use atris::event::{GameOver, GridEvent, SessionEvent};
+use atris::gamemode::GameMode;
+use atris::gamemodes::{custom::{CustomMode, PieceConfiguration}, standard::StandardMode};
+use atris::grid::{Spin, SpinRule};
+use atris::gridengine::GridEngine;
+use atris::gridsession::{GridConfig, GridSeeds};
+use atris::input::{Action, HandlingConfig, PlayerInput};
+use atris::physics::PhysicsConfig;
+use atris::piece::shape::Shape;
+use atris::randomizer::Randomizer;
+use atris::scoringrules::GuidelineScoring;
+use rand::{rngs::StdRng, RngCore};
+
+fn game_mode() -> StandardMode {
+    let mut game_mode = StandardMode::new();
+    GameMode::<StdRng>::initialize(&mut game_mode);
+    game_mode
+}
+
+/// Plays a grid for `ticks` ticks, hard dropping every 7 ticks and moving in between, and returns its events.
+fn play(game_mode: &StandardMode, seeds: GridSeeds, ticks: u64) -> Vec<GridEvent> {
+    let mut engine = GridEngine::new(1, Vector(10, 20), game_mode, GridConfig::default(), seeds);
+    let mut events = engine.step([SessionEvent::PlayerAdded(0, HandlingConfig::default()), SessionEvent::Start]);
+    for tick in 0..ticks {
+        let action = match tick % 7 {
+            0 => Action::HardDrop,
+            1 | 2 => Action::Left,
+            3 => Action::RotateCW,
+            _ => Action::Right,
+        };
+        events.extend(engine.step([
+            SessionEvent::Input(0, PlayerInput::Press(action)),
+            SessionEvent::Input(0, PlayerInput::Release(action)),
+        ]));
+    }
+    events
+}
+
+#[test]
+fn same_seeds_and_inputs_same_game() {
+    let game_mode = game_mode();
+    let seeds = GridSeeds { pieces: 7, grid: 8 };
+    let events = play(&game_mode, seeds, 300);
+    assert!(events.iter().any(|e| matches!(e, GridEvent::Locked(_))));
+    assert_eq!(events, play(&game_mode, seeds, 300));
+}
+
+#[test]
+fn garbage_goes_from_engine_to_engine() {
+    let game_mode = game_mode();
+    let seeds = GridSeeds { pieces: 1, grid: 2 };
+    let config = GridConfig { garbage_delay: 0, ..Default::default() };
+    let mut engine = GridEngine::new(1, Vector(10, 20), &game_mode, config, seeds);
+    engine.step([SessionEvent::PlayerAdded(0, HandlingConfig::default()), SessionEvent::Start]);
+    engine.step([SessionEvent::Garbage(3)]);
+    assert_eq!(engine.incoming_garbage(), 3);
+    let events = engine.step([SessionEvent::Input(0, PlayerInput::Press(Action::HardDrop))]);
+    assert_eq!(engine.incoming_garbage(), 0);
+    assert!(events.contains(&GridEvent::Height(4)) || events.contains(&GridEvent::Height(5)));
+    assert!((0..3).all(|row| (0..10).filter(|&col| engine.grid()[Vector(col, row)].is_none()).count() == 1));
+}
+
+#[test]
+fn initial_rotations_are_not_spins() {
+    let mut game_mode = CustomMode::new(Vec::new(), vec!(PieceConfiguration::new(0, 1, 1))).unwrap();
+    GameMode::<StdRng>::initialize(&mut game_mode);
+    let config = GridConfig { hidden_rows: 0, irs: true, spin_rule: SpinRule::AllSpin, ..Default::default() };
+    let mut engine = GridEngine::new(1, Vector(1, 1), &game_mode, config, GridSeeds { pieces: 1, grid: 2 });
+    // The rotation key stays held, so every figure but the first one is rotated as it spawns, and can't move after
+    let mut events = engine.step([
+        SessionEvent::PlayerAdded(0, HandlingConfig::default()),
+        SessionEvent::Input(0, PlayerInput::Press(Action::RotateCW)),
+        SessionEvent::Start,
+    ]);
+    for _ in 0..100 {
+        events.extend(engine.step([
+            SessionEvent::Input(0, PlayerInput::Press(Action::HardDrop)),
+            SessionEvent::Input(0, PlayerInput::Release(Action::HardDrop)),
+        ]));
+    }
+    let spins: Vec<Spin> = events.iter().filter_map(|e| match e {
+        GridEvent::Locked(lock) => Some(lock.spin),
+        _ => None,
+    }).collect();
+    assert!(spins.len() > 2);
+    assert_eq!(spins[0], Spin::Mini);
+    assert!(spins[1..].iter().all(|&spin| spin == Spin::None));
+}
+
+#[test]
+fn hold_and_queue_are_sent() {
+    let game_mode = game_mode();
+    let seeds = GridSeeds { pieces: 3, grid: 4 };
+    let mut engine = GridEngine::new(1, Vector(10, 20), &game_mode, GridConfig::default(), seeds);
+    let events = engine.step([SessionEvent::PlayerAdded(0, HandlingConfig::default()), SessionEvent::Start]);
+    let queues: Vec<&Vec<Shape>> = events.iter().filter_map(|e| match e {
+        GridEvent::Queue(shapes) => Some(shapes),
+        _ => None,
+    }).collect();
+    // The queue is sent once filled, then once the first figure took its first piece
+    assert_eq!(queues.len(), 2);
+    assert_eq!(queues[0].len(), 5);
+    assert_eq!(queues[1][..4], queues[0][1..]);
+    assert_eq!(engine.figure(0).unwrap().piece().shape(), &queues[0][0]);
+    let spawned = engine.figure(0).unwrap().piece().shape().clone();
+    let next = queues[1].clone();
+    let events = engine.step([SessionEvent::Input(0, PlayerInput::Press(Action::Hold))]);
+    assert!(events.contains(&GridEvent::Hold(0, spawned)));
+    assert!(events.iter().any(|e| matches!(e, GridEvent::Queue(shapes) if shapes[..4] == next[1..])));
+    assert_eq!(engine.figure(0).unwrap().piece().shape(), &next[0]);
+}
+
+#[test]
+fn ghosts_are_sent_when_they_change() {
+    let game_mode = game_mode();
+    let seeds = GridSeeds { pieces: 5, grid: 6 };
+    let mut engine = GridEngine::new(1, Vector(10, 20), &game_mode, GridConfig::default(), seeds);
+    let ghost = |events: &[GridEvent]| events.iter().find_map(|e| match e {
+        GridEvent::Ghost(0, ghost) => Some(ghost.as_ref().map(|shape| shape.positions().clone())),
+        _ => None,
+    });
+    let events = engine.step([SessionEvent::PlayerAdded(0, HandlingConfig::default()), SessionEvent::Start]);
+    let spawned = engine.ghost(0).unwrap().positions().clone();
+    assert_eq!(ghost(&events), Some(Some(spawned.clone())));
+    // Falling doesn't change the ghost, moving does
+    assert_eq!(ghost(&engine.step([])), None);
+    let events = engine.step([SessionEvent::Input(0, PlayerInput::Press(Action::Left))]);
+    let moved: Vec<_> = spawned.iter().map(|&p| p + Vector(-1, 0)).collect();
+    assert_eq!(ghost(&events), Some(Some(moved)));
+    let events = engine.step([SessionEvent::Input(0, PlayerInput::Press(Action::HardDrop))]);
+    assert_eq!(ghost(&events), Some(None));
+}
+
+#[test]
+fn attack_follows_the_difficult_clears_of_the_scoring_rule() {
+    let mut scoring_rule = GuidelineScoring::new();
+    scoring_rule.set_difficult_lines(5);
+    let mut game_mode = CustomMode::new(Vec::new(), vec!(PieceConfiguration::new(0, 5, 1))).unwrap();
+    game_mode.set_scoring_rule(Box::new(scoring_rule));
+    GameMode::<StdRng>::initialize(&mut game_mode);
+    let seeds = GridSeeds { pieces: 1, grid: 2 };
+    let engine = GridEngine::new(1, Vector(10, 20), &game_mode, GridConfig::default(), seeds);
+    assert_eq!(engine.attack().difficult_lines(), 5);
+    let standard = self::game_mode();
+    let engine = GridEngine::new(1, Vector(10, 20), &standard, GridConfig::default(), seeds);
+    assert_eq!(engine.attack().difficult_lines(), 4);
+}
+
+/// A randomizer always picking the last piece (the I among the trominoes).
+#[derive(Debug)]
+struct LastPiece;
+
+impl Randomizer for LastPiece {
+    fn id(&self) -> &'static str { "last" }
+
+    fn next(&mut self, n: usize, _rng: &mut dyn RngCore) -> usize { n - 1 }
+
+    fn restarted(&self) -> Box<dyn Randomizer> { Box::new(LastPiece) }
+}
+
+#[test]
+fn initial_rotations_can_avoid_block_outs() {
+    let mut game_mode = CustomMode::new(Vec::new(), vec!(PieceConfiguration::new(0, 3, 1))).unwrap();
+    game_mode.set_randomizer(Box::new(LastPiece));
+    game_mode.set_level_curve(None);
+    GameMode::<StdRng>::initialize(&mut game_mode);
+    let play = |irs| {
+        let physics = PhysicsConfig { clear_delay: 0, ..Default::default() };
+        let config = GridConfig { physics, hidden_rows: 0, irs, ..Default::default() };
+        let mut engine = GridEngine::new(1, Vector(3, 4), &game_mode, config, GridSeeds { pieces: 1, grid: 2 });
+        engine.step([SessionEvent::PlayerAdded(0, HandlingConfig::default()), SessionEvent::Start]);
+        assert_eq!(engine.figure(0).unwrap().cells().positions(), &vec!(Vector(0, 3), Vector(1, 3), Vector(2, 3)));
+        // The first I clears the bottom row, and the rotation key is held until the next one spawns
+        engine.step([SessionEvent::Input(0, PlayerInput::Press(Action::HardDrop))]);
+        engine.step([SessionEvent::Input(0, PlayerInput::Press(Action::RotateCW))]);
+        // Flat, the next I overlaps the left column, but standing up it fits in the middle one
+        let rock = RockType{};
+        engine.raise((0..4).map(|_| vec!(Some(Block::new(&rock)), None, None)).collect());
+        engine.step([]);
+        (engine.game_over(), engine.figure(0).map(|fig| fig.cells().positions().clone()))
+    };
+    assert_eq!(play(false).0, Some(GameOver::BlockOut));
+    assert_eq!(play(true), (None, Some(vec!(Vector(1, 0), Vector(1, 1), Vector(1, 2)))));
+}
@@ -0,0 +1,21 @@
+use atris::block::BlockType;
+use atris::blocktypes::{rock::RockType, standard::StandardType};
+use atris::gamemode::GameMode;
+use atris::gamemodes::custom::{CustomMode, PieceConfiguration};
+use rand::{rngs::StdRng, SeedableRng};
+
+#[test]
+fn custom_mode_picks_configurations_by_weight() {
+    let block_types: Vec<Box<dyn BlockType>> = vec!(Box::new(StandardType{}), Box::new(RockType{}));
+    let configurations = vec!(PieceConfiguration::new(0, 4, 1), PieceConfiguration::new(1, 4, 3));
+    let mut game_mode = CustomMode::new(block_types, configurations).unwrap();
+    GameMode::<StdRng>::initialize(&mut game_mode);
+    let mut rng = StdRng::seed_from_u64(10);
+    let mut randomizer = GameMode::<StdRng>::randomizer(&game_mode);
+    let n = 10000;
+    let rocks = (0..n)
+        .filter(|_| game_mode.next_piece(randomizer.as_mut(), &mut rng).blocks()[0].get_type().id() == "Rock")
+        .count();
+    let expected = game_mode.probability(1).unwrap() * n as f64;
+    assert!((rocks as f64 - expected).abs() < 0.03 * n as f64);
+}
@@ -0,0 +1,48 @@
+use atris::block::Block;
+use atris::blocktypes::rock::RockType;
+use atris::garbage::{GarbageGenerator, GarbageStyle};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn holes(rows: &[Vec<Option<Block>>]) -> Vec<usize> {
+    rows.iter().map(|row| row.iter().position(|cell| cell.is_none()).unwrap()).collect()
+}
+
+#[test]
+fn clean_garbage_keeps_its_hole() {
+    let bt = RockType{};
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut generator = GarbageGenerator::new(GarbageStyle::Clean);
+    for _ in 0..10 {
+        let holes = holes(&generator.rows(4, 10, &bt, &mut rng));
+        assert!(holes.iter().all(|&hole| hole == holes[0]));
+    }
+}
+
+#[test]
+fn messy_garbage_follows_its_chance() {
+    let bt = RockType{};
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut generator = GarbageGenerator::new(GarbageStyle::Messy(0));
+    let first = holes(&generator.rows(3, 10, &bt, &mut rng));
+    let second = holes(&generator.rows(3, 10, &bt, &mut rng));
+    assert!(first.iter().chain(second.iter()).all(|&hole| hole == first[0]));
+    generator.set_style(GarbageStyle::Messy(100));
+    let all = holes(&generator.rows(8, 10, &bt, &mut rng));
+    assert!(all.windows(2).all(|pair| pair[0] != pair[1]));
+    // Chances above 100% are 100%
+    generator.set_style(GarbageStyle::Messy(u32::MAX));
+    let all = holes(&generator.rows(8, 10, &bt, &mut rng));
+    assert!(all.windows(2).all(|pair| pair[0] != pair[1]));
+}
+
+#[test]
+fn same_seed_same_garbage() {
+    let bt = RockType{};
+    let generate = |seed| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut generator = GarbageGenerator::new(GarbageStyle::Cheese);
+        holes(&generator.rows(20, 10, &bt, &mut rng))
+    };
+    assert_eq!(generate(3), generate(3));
+    assert_ne!(generate(3), generate(4));
+}
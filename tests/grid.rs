@@ -0,0 +1,26 @@
+use atris::algebra::Vector;
+use atris::block::Block;
+use atris::blocktypes::{rock::RockType, standard::StandardType};
+use atris::grid::{Figure, Grid};
+use atris::piece::{shape::Tetromino, Piece};
+use atris::rotationsystems::SuperRotationSystem;
+
+#[test]
+fn rotated_pieces_lock_each_block_in_its_cell() {
+    let (standard, rock) = (StandardType{}, RockType{});
+    let srs = SuperRotationSystem::new();
+    let mut grid = Grid::new(Vector(10, 20));
+    // An I with a rock on its left end, which ends up on top once rotated clockwise
+    let shape = Tetromino::I.shape();
+    let left = *shape.iter().min_by_key(|p| p.0).unwrap();
+    let blocks = shape.iter().map(|&p| Block::new(if p == left { &rock } else { &standard })).collect();
+    let mut fig = Figure::with_rotation_system(Piece::new(blocks, shape), Vector(4, 10), &srs);
+    assert!(grid.try_rotate(&mut fig, -1, &srs).success);
+    let cells = fig.cells();
+    let top = *cells.iter().max_by_key(|p| p.1).unwrap();
+    grid.place(&fig);
+    for &p in cells.iter() {
+        let id = grid[Vector(p.0 as usize, p.1 as usize)].as_ref().unwrap().block_type.id();
+        assert_eq!(id, if p == top { "Rock" } else { "Standard" });
+    }
+}
@@ -0,0 +1,35 @@
+use atris::algebra::Vector;
+use atris::blocktypes::standard::StandardType;
+use atris::grid::{Figure, Grid};
+use atris::level::LevelCurve;
+use atris::physics::{Physics, PhysicsConfig};
+use atris::piece::{shape::Tetromino, Piece};
+
+#[test]
+fn nes_levels_fall_at_their_frames_per_row() {
+    let table = [
+        (0, 48), (1, 43), (2, 38), (3, 33), (4, 28), (5, 23), (6, 18), (7, 13), (8, 8), (9, 6),
+        (10, 5), (13, 4), (16, 3), (19, 2), (29, 1),
+    ];
+    let nes = LevelCurve::nes();
+    let bt = StandardType{};
+    let grid = Grid::new(Vector(10, 40));
+    for tps in [60, 30] {
+        for &(level, frames) in table.iter() {
+            let mut config = PhysicsConfig::default();
+            nes.speed(level).apply(&mut config, tps);
+            let mut fig = Figure::new(Piece::uniform(&bt, Tetromino::O.shape()), Vector(4, 30));
+            let mut physics = Physics::new();
+            let mut ticks = 0;
+            // 4 rows, so that half ticks per row add up to whole ticks at 30 TPS
+            while fig.position.1 > 26 {
+                physics.step(&config, &grid, &mut fig);
+                ticks += 1;
+            }
+            assert_eq!(fig.position.1, 26, "level {level} at {tps} TPS");
+            assert_eq!(ticks * 60, 4 * frames * tps, "level {level} at {tps} TPS");
+        }
+    }
+    // Every level is strictly faster than the one before
+    assert!(table.windows(2).all(|pair| nes.speed(pair[1].0).gravity_frames < nes.speed(pair[0].0).gravity_frames));
+}
@@ -0,0 +1,38 @@
+use std::collections::HashSet;
+
+use atris::gamemode::GameMode;
+use atris::gamemodes::standard::StandardMode;
+use atris::piece::shape::Tetromino;
+use atris::randomizer::Randomizer;
+use atris::randomizers::{Bag, History, PureRandom};
+use rand::{rngs::StdRng, SeedableRng};
+
+#[test]
+fn standard_mode_deals_seven_bags() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut game_mode = StandardMode::new();
+    GameMode::<StdRng>::initialize(&mut game_mode);
+    let mut randomizer = GameMode::<StdRng>::randomizer(&game_mode);
+    for _ in 0..10 {
+        let bag: HashSet<Tetromino> = (0..7)
+            .map(|_| Tetromino::of(game_mode.next_piece(randomizer.as_mut(), &mut rng).shape()).unwrap())
+            .collect();
+        assert_eq!(bag.len(), 7);
+    }
+}
+
+#[test]
+fn restarted_randomizers_repeat_their_sequence() {
+    let randomizers: Vec<Box<dyn Randomizer>> = vec!(
+        Box::new(Bag::new(2)),
+        Box::new(History::new(4, 6)),
+        Box::new(PureRandom{}),
+    );
+    for randomizer in randomizers {
+        let sequence = |mut randomizer: Box<dyn Randomizer>| {
+            let mut rng = StdRng::seed_from_u64(3);
+            (0..30).map(|_| randomizer.next(7, &mut rng)).collect::<Vec<usize>>()
+        };
+        assert_eq!(sequence(randomizer.restarted()), sequence(randomizer.restarted()));
+    }
+}
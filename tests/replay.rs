@@ -0,0 +1,126 @@
+use std::{thread, time::{Duration, Instant}};
+
+use atris::algebra::Vector;
+use atris::atrissession::AtrisSession;
+use atris::clock::ManualClock;
+use atris::event::SessionEvent;
+use atris::gamemode::GameMode;
+use atris::gamemodes::standard::StandardMode;
+use atris::garbage::GarbageStyle;
+use atris::gridengine::GridEngine;
+use atris::gridsession::{GridConfig, GridSeeds};
+use atris::input::{Action, HandlingConfig, PlayerInput};
+use atris::replay::{GridReplay, Replay, ReplayError};
+use atris::rotationsystems::NintendoRotationSystem;
+use futures::executor::ThreadPool;
+use rand::rngs::StdRng;
+
+fn game_mode() -> StandardMode {
+    let mut game_mode = StandardMode::new();
+    GameMode::<StdRng>::initialize(&mut game_mode);
+    game_mode
+}
+
+/// Plays and records a grid for `ticks` ticks, moving and hard dropping, and receiving garbage now and then. The
+/// recording claims the grid was played with `recorded_config`.
+fn record<'a>(game_mode: &'a StandardMode, config: &GridConfig, recorded_config: &GridConfig, grid_id: usize,
+        ticks: u64) -> (GridEngine<'a>, GridReplay) {
+    let seeds = GridSeeds { pieces: grid_id as u64, grid: 100 + grid_id as u64 };
+    let mut engine = GridEngine::new(grid_id, Vector(10, 20), game_mode, config.clone(), seeds);
+    let mut replay = GridReplay::new(grid_id, Vector(10, 20), game_mode, recorded_config.clone(), seeds);
+    engine.start();
+    for tick in 0..ticks {
+        let mut messages = Vec::new();
+        match tick % 9 {
+            0 if tick == 0 => messages.push(SessionEvent::PlayerAdded(0, HandlingConfig::default())),
+            0 => messages.push(SessionEvent::Input(0, PlayerInput::Press(Action::HardDrop))),
+            2 => messages.push(SessionEvent::Input(0, PlayerInput::Press(Action::Left))),
+            4 => messages.push(SessionEvent::Input(0, PlayerInput::Release(Action::Left))),
+            5 if tick % 4 == 1 => messages.push(SessionEvent::Garbage(2)),
+            6 => messages.push(SessionEvent::Input(0, PlayerInput::Press(Action::RotateCCW))),
+            _ => (),
+        }
+        replay.record(&messages);
+        engine.step(messages);
+    }
+    replay.finish(&engine);
+    (engine, replay)
+}
+
+#[test]
+fn replays_play_the_same_game() {
+    let game_mode = game_mode();
+    let config = GridConfig { garbage_style: GarbageStyle::Messy(30), ..Default::default() };
+    let (engines, grids): (Vec<_>, Vec<_>) = [3, 14].into_iter()
+        .map(|grid_id| record(&game_mode, &config, &config, grid_id, 400)).unzip();
+    let replay = Replay { seed: 5, grids };
+    let loaded = Replay::from_bytes(&replay.to_bytes()).unwrap();
+    assert_eq!(loaded.grids, replay.grids);
+    let replayed = loaded.play(&game_mode).unwrap();
+    for (engine, replayed) in engines.iter().zip(replayed.iter()) {
+        assert!(engine.lines() > 0 || engine.score() > 0);
+        assert_eq!(replayed.checksum(), engine.checksum());
+        assert_eq!(replayed.score(), engine.score());
+    }
+}
+
+#[test]
+fn replays_with_other_settings_are_caught() {
+    let game_mode = game_mode();
+    let config = GridConfig::default();
+    let (engine, grid) = record(&game_mode, &config, &GridConfig { garbage_cap: 1, ..config.clone() }, 1, 200);
+    let other = Replay { seed: 0, grids: vec!(grid) };
+    assert!(matches!(other.play(&game_mode), Err(ReplayError::ChecksumMismatch { grid: 1, expected, .. })
+        if expected == engine.checksum()));
+    let (_, grid) = record(&game_mode, &config, &config, 1, 200);
+    let replay = Replay { seed: 0, grids: vec!(grid) };
+    assert!(replay.play(&game_mode).is_ok());
+    let mut other_mode = self::game_mode();
+    other_mode.set_rotation_system(Box::new(NintendoRotationSystem::new()));
+    assert_eq!(replay.play(&other_mode).unwrap_err(), ReplayError::GameModeMismatch { grid: 1 });
+    let mut bytes = replay.to_bytes();
+    bytes[4] = 1;
+    assert_eq!(Replay::from_bytes(&bytes).unwrap_err(), ReplayError::UnsupportedVersion(1));
+    bytes[0] = b'X';
+    assert_eq!(Replay::from_bytes(&bytes).unwrap_err(), ReplayError::NotAReplay);
+}
+
+#[test]
+fn overlong_integers_are_invalid() {
+    let replay = |seed: &[u8]| [b"ATRP\x02".as_slice(), seed, &[0]].concat();
+    let max = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+    assert_eq!(Replay::from_bytes(&replay(&max)).unwrap().seed, u64::MAX);
+    // Bits past the 64th
+    let mut overflow = max;
+    overflow[9] = 0x02;
+    assert_eq!(Replay::from_bytes(&replay(&overflow)).unwrap_err(), ReplayError::Invalid("integer"));
+    // More than 10 bytes
+    let mut overlong = max;
+    overlong[9] = 0x81;
+    assert_eq!(Replay::from_bytes(&replay(&[&overlong[..], &[0x00]].concat())).unwrap_err(),
+        ReplayError::Invalid("integer"));
+}
+
+#[test]
+fn session_replays_keep_the_config_of_their_grids() {
+    let mut session = AtrisSession::with_seed(Box::new(game_mode()), ThreadPool::new().unwrap(), 9);
+    session.set_clock(Box::new(ManualClock::new()));
+    session.set_recording(true);
+    session.set_grid_config(GridConfig { hidden_rows: 2, ..Default::default() });
+    let grid_id = session.create_grid(Vector(10, 20));
+    // Changing the configuration only affects the grids created from now on
+    session.set_grid_config(GridConfig::default());
+    let player_id = session.create_player();
+    session.assign_player(player_id, grid_id);
+    session.start();
+    // Nothing is pressed, so the pieces pile up until the game is over
+    let start = Instant::now();
+    while session.replay().grids.is_empty() {
+        assert!(start.elapsed() < Duration::from_secs(10));
+        session.poll_events();
+        thread::sleep(Duration::from_millis(1));
+    }
+    let replay = Replay::from_bytes(&session.replay().to_bytes()).unwrap();
+    assert_eq!(replay.grids[0].config().hidden_rows, 2);
+    assert!(!replay.play(session.game_mode()).unwrap()[0].is_running());
+}
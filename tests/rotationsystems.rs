@@ -0,0 +1,87 @@
+use atris::algebra::Vector;
+use atris::block::Block;
+use atris::blocktypes::standard::StandardType;
+use atris::grid::{Figure, Grid, Kick};
+use atris::piece::{shape::Tetromino, Piece};
+use atris::rotationsystem::{KickTable, RotationSystem};
+use atris::rotationsystems::{ArikaRotationSystem, NintendoRotationSystem, SuperRotationSystem};
+
+#[test]
+fn srs_floor_kick() {
+    let bt = StandardType{};
+    let srs = SuperRotationSystem::new();
+    let grid = Grid::new(Vector(10, 20));
+    let mut fig = Figure::with_rotation_system(Piece::uniform(&bt, Tetromino::T.shape()), Vector(4, 0), &srs);
+    // 0->R pushes the stem below the floor, so the third test (-1, +1) is the first one that fits
+    let m = grid.try_rotate(&mut fig, -1, &srs);
+    assert!(m.success);
+    assert_eq!(m.kick, Some(Kick { index: 2, offset: Vector(-1, 1), last: false }));
+    assert_eq!((fig.position, fig.rotation()), (Vector(3, 1), 3));
+}
+
+#[test]
+fn ars_center_column_rule() {
+    let bt = StandardType{};
+    let ars = ArikaRotationSystem::new();
+    let shape = Tetromino::T.shape();
+
+    // Obstructed in the center column first: no kick
+    let mut grid = Grid::new(Vector(10, 20));
+    grid.put(Vector(5, 11), Block::new(&bt));
+    let mut fig = Figure::with_rotation_system(Piece::uniform(&bt, shape.clone()), Vector(5, 10), &ars);
+    assert!(!grid.try_rotate(&mut fig, -1, &ars).success);
+
+    // Obstructed in the left column: kicked to the right
+    let mut grid = Grid::new(Vector(10, 20));
+    grid.put(Vector(4, 10), Block::new(&bt));
+    let mut fig = Figure::with_rotation_system(Piece::uniform(&bt, shape), Vector(5, 10), &ars);
+    let m = grid.try_rotate(&mut fig, -1, &ars);
+    assert_eq!(m.kick, Some(Kick { index: 1, offset: Vector(1, 0), last: false }));
+    assert_eq!(fig.position, Vector(6, 10));
+}
+
+#[test]
+fn nrs_never_kicks() {
+    let bt = StandardType{};
+    let nrs = NintendoRotationSystem::new();
+    let grid = Grid::new(Vector(10, 20));
+    let mut fig = Figure::with_rotation_system(Piece::uniform(&bt, Tetromino::I.shape()), Vector(5, 0), &nrs);
+    assert!(!grid.try_rotate(&mut fig, 1, &nrs).success);
+    fig.position = Vector(5, 2);
+    assert!(grid.try_rotate(&mut fig, 1, &nrs).success);
+    assert!(grid.try_rotate(&mut fig, 1, &nrs).success);
+    assert_eq!(fig.shape().positions(), nrs.spawn_shape(&Tetromino::I.shape()).positions());
+}
+
+#[test]
+fn srs_generated_kicks_next_to_wall() {
+    let bt = StandardType{};
+    let srs = SuperRotationSystem::new();
+    let grid = Grid::new(Vector(10, 20));
+    let i6 = atris::piece::shape::Shape::from_iter((0..6).map(|y| Vector(0, y)));
+    let mut fig = Figure::with_rotation_system(Piece::uniform(&bt, i6), Vector(5, 10), &srs);
+    assert!(grid.try_rotate(&mut fig, -1, &srs).success);
+    // Push the vertical line against the left wall, then rotate it back
+    while grid.try_move(&mut fig, Vector(-1, 0)).success {}
+    let m = grid.try_rotate(&mut fig, 1, &srs);
+    assert!(m.success);
+    // The line needs to move 2 cells right, from the column 0 to the column 2, to fit flat against the wall
+    assert_eq!(m.kick, Some(Kick { index: 2, offset: Vector(2, 0), last: false }));
+    assert_eq!((fig.position, fig.rotation()), (Vector(2, 10), 0));
+    assert_eq!(fig.cells().positions(), &(0..6).map(|x| Vector(x, 10)).collect::<Vec<_>>());
+}
+
+#[test]
+fn every_rotation_system_overrides_kicks() {
+    let bt = StandardType{};
+    let grid = Grid::new(Vector(10, 20));
+    let mut kicks = KickTable::new();
+    kicks.set(0, 1, vec!(Vector(0, 0), Vector(0, 2)));
+    let mut nrs = NintendoRotationSystem::new();
+    assert_eq!(nrs.set_kick_table(Tetromino::I.shape(), kicks), None);
+    // The I piece can't stand up on the floor without being kicked up
+    let mut fig = Figure::with_rotation_system(Piece::uniform(&bt, Tetromino::I.shape()), Vector(5, 0), &nrs);
+    let m = grid.try_rotate(&mut fig, 1, &nrs);
+    assert_eq!(m.kick, Some(Kick { index: 1, offset: Vector(0, 2), last: true }));
+    assert_eq!((fig.position, fig.rotation()), (Vector(5, 2), 1));
+}
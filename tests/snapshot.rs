@@ -0,0 +1,93 @@
+#![cfg(feature = "serde")]
+
+use atris::algebra::Vector;
+use atris::blocktypes::BlockTypes;
+use atris::event::SessionEvent;
+use atris::gamemode::GameMode;
+use atris::gamemodes::standard::StandardMode;
+use atris::garbage::GarbageStyle;
+use atris::gridengine::GridEngine;
+use atris::gridsession::{GridConfig, GridSeeds};
+use atris::input::{Action, HandlingConfig, PlayerInput};
+use atris::targeting::{Targeting, TargetingStrategy};
+use rand::rngs::StdRng;
+
+fn game_mode() -> StandardMode {
+    let mut game_mode = StandardMode::new();
+    GameMode::<StdRng>::initialize(&mut game_mode);
+    game_mode
+}
+
+/// Returns the messages of a tick: moving, rotating, holding and hard dropping, and garbage now and then.
+fn messages(tick: u64) -> Vec<SessionEvent> {
+    let input = |action| SessionEvent::Input(0, PlayerInput::Press(action));
+    let release = |action| SessionEvent::Input(0, PlayerInput::Release(action));
+    match (tick % 11, tick / 11 % 4) {
+        (0, _) => vec!(input(Action::HardDrop), release(Action::HardDrop)),
+        (1, 0) => vec!(input(Action::Left)),
+        (1, 1) => vec!(input(Action::Right)),
+        (1, 3) => vec!(input(Action::Left), release(Action::Left)),
+        (4, _) => vec!(input(Action::RotateCW), release(Action::RotateCW)),
+        (6, 0 | 2) => vec!(SessionEvent::Garbage(1)),
+        (7, 1) => vec!(input(Action::Hold), release(Action::Hold)),
+        (8, _) => vec!(input(Action::SoftDrop)),
+        (10, _) => vec!(release(Action::Left), release(Action::Right), release(Action::SoftDrop)),
+        _ => Vec::new(),
+    }
+}
+
+#[test]
+fn restored_grids_go_on_the_same() {
+    let game_mode = game_mode();
+    let block_types = BlockTypes::base_types();
+    let config = GridConfig { garbage_style: GarbageStyle::Messy(50), garbage_delay: 3, ..Default::default() };
+    let mut engine = GridEngine::new(1, Vector(10, 20), &game_mode, config, GridSeeds { pieces: 5, grid: 6 });
+    engine.step([SessionEvent::PlayerAdded(0, HandlingConfig::default()), SessionEvent::Start]);
+    let mut restored: Vec<GridEngine> = Vec::new();
+    // Snapshots are taken all along the game, until after it's over
+    for tick in 0..200u64 {
+        if tick % 13 == 0 {
+            let snapshot = serde_json::to_string(&engine).unwrap();
+            let mut deserializer = serde_json::Deserializer::from_str(&snapshot);
+            let restored_engine = GridEngine::restore(&mut deserializer, &game_mode, &block_types).unwrap();
+            assert_eq!(restored_engine.checksum(), engine.checksum());
+            restored.push(restored_engine);
+        }
+        let events = engine.step(messages(tick));
+        for restored_engine in restored.iter_mut() {
+            assert_eq!(restored_engine.step(messages(tick)), events);
+        }
+    }
+    assert!(!engine.is_running());
+    assert!(restored.iter().all(|restored_engine| restored_engine.checksum() == engine.checksum()));
+}
+
+#[test]
+fn unknown_block_types_are_an_error() {
+    let game_mode = game_mode();
+    let seeds = GridSeeds { pieces: 1, grid: 2 };
+    let mut engine = GridEngine::new(1, Vector(10, 20), &game_mode, GridConfig::default(), seeds);
+    engine.step([SessionEvent::PlayerAdded(0, HandlingConfig::default()), SessionEvent::Start]);
+    let snapshot = serde_json::to_string(&engine).unwrap();
+    let block_types = BlockTypes::new();
+    let error = GridEngine::restore(&mut serde_json::Deserializer::from_str(&snapshot), &game_mode, &block_types)
+        .unwrap_err();
+    assert!(error.to_string().contains("unknown block type"));
+}
+
+#[test]
+fn session_state_round_trips() {
+    let mut targeting = Targeting::with_seed(0);
+    targeting.set_badges(true);
+    for grid_id in 0..3 {
+        targeting.add(grid_id);
+    }
+    targeting.set_strategy(1, TargetingStrategy::Manual(2));
+    targeting.attack(1, 4);
+    targeting.knock_out(2);
+    let json = serde_json::to_string(&targeting).unwrap();
+    assert_eq!(serde_json::from_str::<Targeting>(&json).unwrap(), targeting);
+    let config = GridConfig { garbage_style: GarbageStyle::MultiHole(2), ..Default::default() };
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(serde_json::to_string(&serde_json::from_str::<GridConfig>(&json).unwrap()).unwrap(), json);
+}
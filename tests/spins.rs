@@ -0,0 +1,42 @@
+use atris::algebra::Vector;
+use atris::block::Block;
+use atris::grid::{Figure, Grid, Kick, Movement, Spin, SpinRule};
+use atris::piece::{shape::Tetromino, Piece};
+use atris::blocktypes::standard::StandardType;
+use atris::rotationsystems::SuperRotationSystem;
+
+#[test]
+fn all_spin_needs_an_immobile_figure() {
+    let bt = StandardType{};
+    let srs = SuperRotationSystem::new();
+    let mut grid = Grid::new(Vector(6, 6));
+    let fig = Figure::with_rotation_system(Piece::uniform(&bt, Tetromino::L.shape()), Vector(2, 1), &srs);
+    let cells = fig.cells();
+    for x in 0..6 {
+        for y in 0..4 {
+            if !cells.contains(&Vector(x, y)) {
+                grid.put(Vector(x as usize, y as usize), Block::new(&bt));
+            }
+        }
+    }
+    let mut rotation = Movement { success: true, position: fig.position, rotation: 0, kick: None };
+    assert_eq!(SpinRule::AllSpin.detect(&grid, &fig, Some(&rotation)), Spin::Mini);
+    assert_eq!(SpinRule::TSpin.detect(&grid, &fig, Some(&rotation)), Spin::None);
+    rotation.kick = Some(Kick { index: 4, offset: Vector(1, 2), last: true });
+    assert_eq!(SpinRule::AllSpin.detect(&grid, &fig, Some(&rotation)), Spin::Full);
+    // Once the blocks above it are gone, it could move up
+    let grid = Grid::new(Vector(6, 6));
+    assert_eq!(SpinRule::AllSpin.detect(&grid, &fig, Some(&rotation)), Spin::None);
+}
+
+#[test]
+fn t_spin_mini_with_one_front_corner() {
+    let bt = StandardType{};
+    let srs = SuperRotationSystem::new();
+    let mut grid = Grid::new(Vector(10, 20));
+    // T pointing up on the floor, next to a block: both back corners are out of the grid, one front corner is taken
+    let fig = Figure::with_rotation_system(Piece::uniform(&bt, Tetromino::T.shape()), Vector(1, 0), &srs);
+    grid.put(Vector(0, 1), Block::new(&bt));
+    let rotation = Movement { success: true, position: fig.position, rotation: 0, kick: None };
+    assert_eq!(SpinRule::TSpin.detect(&grid, &fig, Some(&rotation)), Spin::Mini);
+}
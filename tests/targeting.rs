@@ -0,0 +1,77 @@
+use atris::algebra::Vector;
+use atris::atrissession::AtrisSession;
+use atris::gamemodes::standard::StandardMode;
+use atris::targeting::{Targeting, TargetingStrategy};
+use futures::executor::ThreadPool;
+
+#[test]
+fn even_targeting_spreads_the_garbage() {
+    let mut targeting = Targeting::with_seed(0);
+    for grid_id in 0..5 {
+        targeting.add(grid_id);
+    }
+    targeting.set_strategy(0, TargetingStrategy::Even);
+    for _ in 0..8 {
+        targeting.attack(0, 1);
+    }
+    assert!((1..5).all(|grid_id| targeting.get(grid_id).unwrap().times_targeted == 2));
+}
+
+#[test]
+fn badges_come_from_kos() {
+    let mut targeting = Targeting::with_seed(0);
+    targeting.set_badges(true);
+    for grid_id in 0..4 {
+        targeting.add(grid_id);
+        targeting.set_strategy(grid_id, TargetingStrategy::Manual((grid_id + 1) % 4));
+    }
+    targeting.attack(2, 1);
+    assert_eq!(targeting.knock_out(3), Some(2));
+    targeting.attack(1, 1);
+    assert_eq!(targeting.knock_out(2), Some(1));
+    // The KO of a grid with a badge point is worth 2 points, for a 25% bonus
+    assert_eq!(targeting.get(1).unwrap().badges, 2);
+    assert_eq!(targeting.attack(1, 4), vec!((0, 5)));
+    // Manual targets fall back to the opponents left
+    assert_eq!(targeting.attack(0, 4), vec!((1, 4)));
+    assert_eq!(targeting.knock_out(1), Some(0));
+    assert_eq!(targeting.alive().collect::<Vec<usize>>(), vec!(0));
+}
+
+#[test]
+fn teams_only_attack_other_teams() {
+    let mut targeting = Targeting::with_seed(0);
+    for grid_id in 0..4 {
+        targeting.add(grid_id);
+        targeting.set_team(grid_id, Some(grid_id % 2));
+    }
+    for _ in 0..10 {
+        assert!(targeting.attack(0, 2).iter().all(|&(target, _)| target % 2 == 1));
+    }
+    targeting.set_split(true);
+    let received = targeting.attack(0, 5);
+    assert_eq!(received.iter().map(|&(_, lines)| lines).sum::<u32>(), 5);
+    assert_eq!(received.iter().map(|&(target, _)| target).collect::<Vec<usize>>(), vec!(1, 3));
+    targeting.knock_out(1);
+    assert_eq!(targeting.sides_alive(), 2);
+    assert_eq!(targeting.attack(2, 5), vec!((3, 5)));
+    targeting.knock_out(3);
+    assert_eq!(targeting.sides_alive(), 1);
+}
+
+#[test]
+fn sessions_with_the_same_seed_pick_the_same_targets() {
+    let targets = |seed| {
+        let mut session = AtrisSession::with_seed(Box::new(StandardMode::new()), ThreadPool::new().unwrap(), seed);
+        let grids: Vec<usize> = (0..5).map(|_| session.create_grid(Vector(10, 20))).collect();
+        session.set_targeting_strategy(grids[1], TargetingStrategy::Even);
+        let mut targeting = session.targeting().clone();
+        session.stop();
+        let picks: Vec<Vec<(usize, u32)>> = (0..20).map(|i| targeting.attack(grids[i % 2], 2)).collect();
+        (grids, picks)
+    };
+    let (grids, picks) = targets(7);
+    assert_eq!(targets(7), (grids.clone(), picks.clone()));
+    assert!(picks.iter().all(|picks| picks.len() == 1));
+    assert_ne!(targets(8).1, picks);
+}